@@ -0,0 +1,1193 @@
+//! Usage and cost reports from the Anthropic Admin API, for API-billed
+//! organizations whose usage claude.ai does not show.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+
+use crate::collector::{Collector, Descriptor, Sample, into_increments};
+use crate::config::{ClaudeApiConfig, ClaudeCodeAnalyticsConfig};
+use crate::retry::{self, RetryPolicy};
+use crate::state::StateStore;
+
+const API_VERSION: &str = "2023-06-01";
+
+/// Name of the API collector's file in the state directory.
+const API_STATE_NAME: &str = "claude_api";
+
+// ============================================================================
+// Admin API Types
+// ============================================================================
+
+/// One page of a paginated Admin API list.
+#[derive(Debug, Deserialize)]
+struct ReportPage<T> {
+    data: Vec<T>,
+    #[serde(default)]
+    has_more: bool,
+    next_page: Option<String>,
+}
+
+/// A time bucket of the usage and cost reports, holding grouped results.
+#[derive(Debug, Deserialize)]
+struct ReportBucket<T> {
+    results: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageResult {
+    model: Option<String>,
+    workspace_id: Option<String>,
+    api_key_id: Option<String>,
+    #[serde(default)]
+    uncached_input_tokens: u64,
+    cache_creation: Option<CacheCreation>,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CacheCreation {
+    #[serde(default)]
+    ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    ephemeral_1h_input_tokens: u64,
+}
+
+/// `amount` is a decimal string in minor currency units (cents).
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    currency: String,
+    amount: String,
+    workspace_id: Option<String>,
+    model: Option<String>,
+    cost_type: Option<String>,
+    token_type: Option<String>,
+}
+
+/// Tokens used through one API key with one model since the report start.
+/// Missing groups (e.g. usage from the Workbench, which has no API key) are
+/// empty strings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiTokenUsage {
+    pub model: String,
+    pub workspace_id: String,
+    pub api_key_id: String,
+    pub uncached_input_tokens: u64,
+    pub cache_creation_5m_input_tokens: u64,
+    pub cache_creation_1h_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Cost of one workspace, model and cost type since the report start, in
+/// whole currency units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiCost {
+    pub workspace_id: String,
+    pub model: String,
+    /// e.g. `tokens`, `web_search` or `code_execution`.
+    pub cost_type: String,
+    /// e.g. `uncached_input_tokens`; empty for costs not billed per token.
+    pub token_type: String,
+    pub currency: String,
+    pub amount: f64,
+}
+
+fn sum_usage(results: Vec<UsageResult>) -> Vec<ApiTokenUsage> {
+    let mut totals: BTreeMap<(String, String, String), ApiTokenUsage> = BTreeMap::new();
+    for result in results {
+        let model = result.model.unwrap_or_default();
+        let workspace_id = result.workspace_id.unwrap_or_default();
+        let api_key_id = result.api_key_id.unwrap_or_default();
+        let usage = totals
+            .entry((model.clone(), workspace_id.clone(), api_key_id.clone()))
+            .or_insert_with(|| ApiTokenUsage {
+                model,
+                workspace_id,
+                api_key_id,
+                ..ApiTokenUsage::default()
+            });
+        usage.uncached_input_tokens += result.uncached_input_tokens;
+        usage.cache_read_input_tokens += result.cache_read_input_tokens;
+        usage.output_tokens += result.output_tokens;
+        if let Some(cache_creation) = result.cache_creation {
+            usage.cache_creation_5m_input_tokens += cache_creation.ephemeral_5m_input_tokens;
+            usage.cache_creation_1h_input_tokens += cache_creation.ephemeral_1h_input_tokens;
+        }
+    }
+    totals.into_values().collect()
+}
+
+fn sum_costs(results: Vec<CostResult>) -> anyhow::Result<Vec<ApiCost>> {
+    let mut totals: BTreeMap<[String; 5], f64> = BTreeMap::new();
+    for result in results {
+        let cents: f64 = result
+            .amount
+            .parse()
+            .with_context(|| format!("Invalid cost amount {:?}", result.amount))?;
+        let key = [
+            result.workspace_id.unwrap_or_default(),
+            result.model.unwrap_or_default(),
+            result.cost_type.unwrap_or_default(),
+            result.token_type.unwrap_or_default(),
+            result.currency,
+        ];
+        *totals.entry(key).or_default() += cents / 100.0;
+    }
+    Ok(totals
+        .into_iter()
+        .map(
+            |([workspace_id, model, cost_type, token_type, currency], amount)| ApiCost {
+                workspace_id,
+                model,
+                cost_type,
+                token_type,
+                currency,
+                amount,
+            },
+        )
+        .collect())
+}
+
+/// Midnight UTC on the first day of `now`'s month.
+fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// One actor's Claude Code activity on one day and terminal.
+#[derive(Debug, Deserialize)]
+struct ClaudeCodeRecord {
+    actor: Actor,
+    #[serde(default)]
+    core_metrics: CoreMetrics,
+    #[serde(default)]
+    tool_actions: BTreeMap<String, ToolActions>,
+    #[serde(default)]
+    model_breakdown: Vec<ModelBreakdown>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum Actor {
+    #[serde(rename = "user_actor")]
+    User { email_address: String },
+    #[serde(rename = "api_actor")]
+    Api { api_key_name: String },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CoreMetrics {
+    num_sessions: u64,
+    lines_of_code: LinesOfCode,
+    commits_by_claude_code: u64,
+    pull_requests_by_claude_code: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LinesOfCode {
+    added: u64,
+    removed: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelBreakdown {
+    model: String,
+    #[serde(default)]
+    tokens: ModelTokens,
+    estimated_cost: Option<EstimatedCost>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ModelTokens {
+    input: u64,
+    output: u64,
+    cache_read: u64,
+    cache_creation: u64,
+}
+
+/// `amount` is in minor currency units (cents).
+#[derive(Debug, Deserialize)]
+struct EstimatedCost {
+    currency: String,
+    amount: f64,
+}
+
+/// Suggestions a Claude Code tool made that were accepted or rejected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct ToolActions {
+    #[serde(default)]
+    pub accepted: u64,
+    #[serde(default)]
+    pub rejected: u64,
+}
+
+/// Tokens and estimated cost of one model, in whole currency units.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelActivity {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub currency: String,
+    pub estimated_cost: f64,
+}
+
+/// One actor's Claude Code activity on one day, summed over terminals.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClaudeCodeActivity {
+    /// User email address or API key name.
+    pub actor: String,
+    /// `user` or `api_key`.
+    pub actor_type: &'static str,
+    pub sessions: u64,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub commits: u64,
+    pub pull_requests: u64,
+    /// Per tool, e.g. `edit_tool` or `write_tool`.
+    pub tool_actions: BTreeMap<String, ToolActions>,
+    /// Per model ID.
+    pub models: BTreeMap<String, ModelActivity>,
+}
+
+/// Sums the records of each actor. Records of actor types this version does
+/// not know are skipped, since they cannot be told apart.
+fn sum_activity(records: Vec<ClaudeCodeRecord>) -> Vec<ClaudeCodeActivity> {
+    let mut totals: BTreeMap<(&'static str, String), ClaudeCodeActivity> = BTreeMap::new();
+    let mut skipped = 0;
+    for record in records {
+        let (actor_type, actor) = match record.actor {
+            Actor::User { email_address } => ("user", email_address),
+            Actor::Api { api_key_name } => ("api_key", api_key_name),
+            Actor::Unknown => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let activity = totals
+            .entry((actor_type, actor.clone()))
+            .or_insert_with(|| ClaudeCodeActivity {
+                actor,
+                actor_type,
+                ..ClaudeCodeActivity::default()
+            });
+
+        let core = record.core_metrics;
+        activity.sessions += core.num_sessions;
+        activity.lines_added += core.lines_of_code.added;
+        activity.lines_removed += core.lines_of_code.removed;
+        activity.commits += core.commits_by_claude_code;
+        activity.pull_requests += core.pull_requests_by_claude_code;
+        for (tool, actions) in record.tool_actions {
+            let total = activity.tool_actions.entry(tool).or_default();
+            total.accepted += actions.accepted;
+            total.rejected += actions.rejected;
+        }
+        for breakdown in record.model_breakdown {
+            let model = activity.models.entry(breakdown.model).or_default();
+            model.input_tokens += breakdown.tokens.input;
+            model.output_tokens += breakdown.tokens.output;
+            model.cache_read_tokens += breakdown.tokens.cache_read;
+            model.cache_creation_tokens += breakdown.tokens.cache_creation;
+            if let Some(cost) = breakdown.estimated_cost {
+                model.estimated_cost += cost.amount / 100.0;
+                model.currency = cost.currency;
+            }
+        }
+    }
+    if skipped > 0 {
+        warn!(
+            records = skipped,
+            "Skipping Claude Code activity of an actor type this version does not recognize"
+        );
+    }
+    totals.into_values().collect()
+}
+
+// ============================================================================
+// Admin Client
+// ============================================================================
+
+/// Fetches organization reports with an Admin API key (`sk-ant-admin...`).
+#[derive(Debug, Clone)]
+pub struct AdminClient {
+    base_url: String,
+    http_client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl AdminClient {
+    /// `base_url` is normally `https://api.anthropic.com`.
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http_client,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Replaces the default retry policy for API requests.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Message token usage since `starting_at`, summed per model, workspace
+    /// and API key.
+    pub async fn fetch_usage(
+        &self,
+        api_key: &str,
+        starting_at: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<ApiTokenUsage>> {
+        self.fetch_usage_report(api_key, starting_at, None).await
+    }
+
+    /// Message token usage from `starting_at` until `ending_at`, e.g. over a
+    /// past month, summed like [`Self::fetch_usage`].
+    pub async fn fetch_usage_between(
+        &self,
+        api_key: &str,
+        starting_at: DateTime<Utc>,
+        ending_at: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<ApiTokenUsage>> {
+        self.fetch_usage_report(api_key, starting_at, Some(ending_at))
+            .await
+    }
+
+    async fn fetch_usage_report(
+        &self,
+        api_key: &str,
+        starting_at: DateTime<Utc>,
+        ending_at: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<Vec<ApiTokenUsage>> {
+        let mut query = vec![
+            ("starting_at", rfc3339(starting_at)),
+            ("bucket_width", "1d".to_string()),
+            ("group_by[]", "model".to_string()),
+            ("group_by[]", "workspace_id".to_string()),
+            ("group_by[]", "api_key_id".to_string()),
+        ];
+        if let Some(ending_at) = ending_at {
+            query.push(("ending_at", rfc3339(ending_at)));
+        }
+        let results = self
+            .fetch_report(
+                api_key,
+                "/v1/organizations/usage_report/messages",
+                &query,
+                "admin.usage_report",
+            )
+            .await
+            .context("Failed to fetch Anthropic usage report")?;
+        Ok(sum_usage(results))
+    }
+
+    /// Cost since `starting_at`, summed per workspace, model and cost type.
+    pub async fn fetch_costs(
+        &self,
+        api_key: &str,
+        starting_at: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<ApiCost>> {
+        let query = [
+            ("starting_at", rfc3339(starting_at)),
+            ("group_by[]", "workspace_id".to_string()),
+            ("group_by[]", "description".to_string()),
+        ];
+        let results = self
+            .fetch_report(
+                api_key,
+                "/v1/organizations/cost_report",
+                &query,
+                "admin.cost_report",
+            )
+            .await
+            .context("Failed to fetch Anthropic cost report")?;
+        sum_costs(results)
+    }
+
+    /// Claude Code activity on `date` (UTC), summed per actor.
+    pub async fn fetch_claude_code_activity(
+        &self,
+        api_key: &str,
+        date: NaiveDate,
+    ) -> anyhow::Result<Vec<ClaudeCodeActivity>> {
+        let query = [
+            ("starting_at", date.format("%Y-%m-%d").to_string()),
+            ("limit", "1000".to_string()),
+        ];
+        let records = self
+            .fetch_pages(
+                api_key,
+                "/v1/organizations/usage_report/claude_code",
+                &query,
+                "admin.claude_code_report",
+            )
+            .await
+            .context("Failed to fetch Claude Code usage report")?;
+        Ok(sum_activity(records))
+    }
+
+    /// Fetches every page of a bucketed report and returns the results of all
+    /// buckets.
+    async fn fetch_report<T: DeserializeOwned>(
+        &self,
+        api_key: &str,
+        path: &str,
+        query: &[(&str, String)],
+        operation: &'static str,
+    ) -> anyhow::Result<Vec<T>> {
+        let buckets: Vec<ReportBucket<T>> =
+            self.fetch_pages(api_key, path, query, operation).await?;
+        Ok(buckets
+            .into_iter()
+            .flat_map(|bucket| bucket.results)
+            .collect())
+    }
+
+    /// Fetches every page of a list and returns the items of all pages.
+    async fn fetch_pages<T: DeserializeOwned>(
+        &self,
+        api_key: &str,
+        path: &str,
+        query: &[(&str, String)],
+        operation: &'static str,
+    ) -> anyhow::Result<Vec<T>> {
+        let base_url = format!("{}{}", self.base_url, path);
+        let mut results = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = reqwest::Url::parse_with_params(&base_url, query)
+                .with_context(|| format!("Invalid Anthropic Admin API URL {base_url}"))?;
+            if let Some(page_token) = &page_token {
+                url.query_pairs_mut().append_pair("page", page_token);
+            }
+            let page: ReportPage<T> = self
+                .retry
+                .run(operation, || async {
+                    let request = self
+                        .http_client
+                        .get(url.clone())
+                        .header("x-api-key", api_key)
+                        .header("anthropic-version", API_VERSION);
+                    retry::send(request, "Anthropic Admin API")
+                        .await?
+                        .json::<ReportPage<T>>()
+                        .await
+                        .context("Failed to parse Anthropic Admin API report")
+                })
+                .await?;
+
+            results.extend(page.data);
+            match page.next_page {
+                Some(next_page) if page.has_more => page_token = Some(next_page),
+                _ => return Ok(results),
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Claude API Metrics Collection
+// ============================================================================
+
+static API_TOKENS: Descriptor = Descriptor {
+    name: "claude.api.tokens",
+    description: "Tokens used through the Anthropic API, by token type",
+    unit: "{token}",
+};
+
+static API_COST: Descriptor = Descriptor {
+    name: "claude.api.cost",
+    description: "Anthropic API cost since the start of the month",
+    unit: "{currency}",
+};
+
+/// Token totals already reported as counter increments in `month_start`'s
+/// month, persisted so that a restart does not count the month so far a
+/// second time.
+#[derive(Debug, Default)]
+struct ReportedTokens {
+    month_start: Option<DateTime<Utc>>,
+    /// Keyed by model, workspace ID, API key ID and token type.
+    series: BTreeMap<[String; 4], u64>,
+}
+
+/// On-disk form of [`ReportedTokens`]; JSON object keys must be strings.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReportedTokensState {
+    month_start: Option<DateTime<Utc>>,
+    series: Vec<ReportedSeries>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReportedSeries {
+    model: String,
+    workspace_id: String,
+    api_key_id: String,
+    token_type: String,
+    tokens: u64,
+}
+
+impl From<ReportedTokensState> for ReportedTokens {
+    fn from(state: ReportedTokensState) -> Self {
+        Self {
+            month_start: state.month_start,
+            series: state
+                .series
+                .into_iter()
+                .map(|s| {
+                    (
+                        [s.model, s.workspace_id, s.api_key_id, s.token_type],
+                        s.tokens,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&ReportedTokens> for ReportedTokensState {
+    fn from(reported: &ReportedTokens) -> Self {
+        Self {
+            month_start: reported.month_start,
+            series: reported
+                .series
+                .iter()
+                .map(
+                    |([model, workspace_id, api_key_id, token_type], tokens)| ReportedSeries {
+                        model: model.clone(),
+                        workspace_id: workspace_id.clone(),
+                        api_key_id: api_key_id.clone(),
+                        token_type: token_type.clone(),
+                        tokens: *tokens,
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+pub struct ClaudeApiCollector {
+    config: ClaudeApiConfig,
+    client: AdminClient,
+    store: StateStore,
+    /// Loaded from the state store on the first run.
+    reported: Mutex<Option<ReportedTokens>>,
+}
+
+impl ClaudeApiCollector {
+    pub fn new(
+        config: ClaudeApiConfig,
+        store: StateStore,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Self> {
+        let client = AdminClient::new(config.base_url.clone(), config.timeout)?.with_retry(retry);
+        Ok(Self {
+            config,
+            client,
+            store,
+            reported: Mutex::default(),
+        })
+    }
+
+    /// Runs `f` on the reported totals, loading them on first use.
+    fn with_reported<T>(&self, f: impl FnOnce(&mut ReportedTokens) -> T) -> T {
+        let mut reported = self.reported.lock().unwrap();
+        f(reported.get_or_insert_with(|| {
+            self.store
+                .load::<ReportedTokensState>(API_STATE_NAME)
+                .into()
+        }))
+    }
+
+    /// The month reported so far, when it ended before `month_start`. Its
+    /// report keeps filling in after the last run of the month, so it is
+    /// fetched once more before the counters move on.
+    fn unfinished_month(&self, month_start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.with_reported(|reported| reported.month_start.filter(|start| *start < month_start))
+    }
+
+    /// Counter increments for the month-to-date `usage`, relative to what was
+    /// reported before. A new month starts every series from zero.
+    fn token_samples(
+        &self,
+        month_start: DateTime<Utc>,
+        usage: &[ApiTokenUsage],
+    ) -> anyhow::Result<Vec<Sample>> {
+        self.with_reported(|reported| {
+            if reported.month_start != Some(month_start) {
+                reported.month_start = Some(month_start);
+                reported.series.clear();
+            }
+
+            let mut samples = Vec::new();
+            for usage in usage {
+                let token_types = [
+                    ("uncached_input", usage.uncached_input_tokens),
+                    ("cache_creation_5m", usage.cache_creation_5m_input_tokens),
+                    ("cache_creation_1h", usage.cache_creation_1h_input_tokens),
+                    ("cache_read", usage.cache_read_input_tokens),
+                    ("output", usage.output_tokens),
+                ];
+                for (token_type, tokens) in token_types {
+                    let key = [
+                        usage.model.clone(),
+                        usage.workspace_id.clone(),
+                        usage.api_key_id.clone(),
+                        token_type.to_string(),
+                    ];
+                    let previous = reported.series.insert(key, tokens).unwrap_or_default();
+                    samples.push(
+                        Sample::counter(&API_TOKENS, tokens.saturating_sub(previous))
+                            .with_attribute("model", usage.model.clone())
+                            .with_attribute("workspace_id", usage.workspace_id.clone())
+                            .with_attribute("api_key_id", usage.api_key_id.clone())
+                            .with_attribute("type", token_type),
+                    );
+                }
+            }
+
+            self.store
+                .save(API_STATE_NAME, &ReportedTokensState::from(&*reported))?;
+            Ok(samples)
+        })
+    }
+}
+
+#[async_trait]
+impl Collector for ClaudeApiCollector {
+    fn name(&self) -> &'static str {
+        "claude_api"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude API"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "claude-api-usage"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "claude_api_usage_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Fetching Anthropic API usage and cost reports");
+
+        let api_key = std::env::var(&self.config.admin_key_env).with_context(|| {
+            format!("{} environment variable not set", self.config.admin_key_env)
+        })?;
+
+        let month_start = month_start(Utc::now());
+        let mut samples = Vec::new();
+        if let Some(previous) = self.unfinished_month(month_start) {
+            let usage = self
+                .client
+                .fetch_usage_between(&api_key, previous, month_start)
+                .await
+                .context("Failed to complete the previous month's usage")?;
+            samples.extend(self.token_samples(previous, &usage)?);
+        }
+        let (usage, costs) = futures_util::future::try_join(
+            self.client.fetch_usage(&api_key, month_start),
+            self.client.fetch_costs(&api_key, month_start),
+        )
+        .await?;
+
+        samples.extend(self.token_samples(month_start, &usage)?);
+        for cost in &costs {
+            samples.push(
+                Sample::f64(&API_COST, cost.amount)
+                    .with_attribute("workspace_id", cost.workspace_id.clone())
+                    .with_attribute("model", cost.model.clone())
+                    .with_attribute("cost_type", cost.cost_type.clone())
+                    .with_attribute("token_type", cost.token_type.clone())
+                    .with_attribute("currency", cost.currency.clone()),
+            );
+        }
+
+        info!(
+            usage_groups = usage.len(),
+            cost_groups = costs.len(),
+            "Fetched Anthropic API usage and cost reports"
+        );
+
+        Ok(samples)
+    }
+}
+
+// ============================================================================
+// Claude Code Analytics Metrics Collection
+// ============================================================================
+
+static ANALYTICS_SESSIONS: Descriptor = Descriptor {
+    name: "claude_code.analytics.sessions",
+    description: "Claude Code sessions started, by actor",
+    unit: "{session}",
+};
+
+static ANALYTICS_LINES: Descriptor = Descriptor {
+    name: "claude_code.analytics.lines",
+    description: "Lines of code added or removed by Claude Code, by actor",
+    unit: "{line}",
+};
+
+static ANALYTICS_COMMITS: Descriptor = Descriptor {
+    name: "claude_code.analytics.commits",
+    description: "Commits created with Claude Code, by actor",
+    unit: "{commit}",
+};
+
+static ANALYTICS_PULL_REQUESTS: Descriptor = Descriptor {
+    name: "claude_code.analytics.pull_requests",
+    description: "Pull requests created with Claude Code, by actor",
+    unit: "{pull_request}",
+};
+
+static ANALYTICS_TOOL_ACTIONS: Descriptor = Descriptor {
+    name: "claude_code.analytics.tool_actions",
+    description: "Claude Code tool suggestions accepted or rejected, by actor",
+    unit: "{action}",
+};
+
+static ANALYTICS_TOKENS: Descriptor = Descriptor {
+    name: "claude_code.analytics.tokens",
+    description: "Tokens used by Claude Code, by actor and model",
+    unit: "{token}",
+};
+
+static ANALYTICS_COST: Descriptor = Descriptor {
+    name: "claude_code.analytics.estimated_cost",
+    description: "Estimated Claude Code cost, by actor and model",
+    unit: "{currency}",
+};
+
+/// Name of the analytics collector's file in the state directory.
+const ANALYTICS_STATE_NAME: &str = "claude_code_analytics";
+
+/// Daily totals as counter samples carrying the whole day's value.
+fn activity_samples(activity: &ClaudeCodeActivity) -> Vec<Sample> {
+    let actor = |sample: Sample| {
+        sample
+            .with_attribute("actor", activity.actor.clone())
+            .with_attribute("actor_type", activity.actor_type)
+    };
+
+    let mut samples = vec![
+        actor(Sample::counter(&ANALYTICS_SESSIONS, activity.sessions)),
+        actor(Sample::counter(&ANALYTICS_LINES, activity.lines_added))
+            .with_attribute("change", "added"),
+        actor(Sample::counter(&ANALYTICS_LINES, activity.lines_removed))
+            .with_attribute("change", "removed"),
+        actor(Sample::counter(&ANALYTICS_COMMITS, activity.commits)),
+        actor(Sample::counter(
+            &ANALYTICS_PULL_REQUESTS,
+            activity.pull_requests,
+        )),
+    ];
+    for (tool, actions) in &activity.tool_actions {
+        for (decision, count) in [
+            ("accepted", actions.accepted),
+            ("rejected", actions.rejected),
+        ] {
+            samples.push(
+                actor(Sample::counter(&ANALYTICS_TOOL_ACTIONS, count))
+                    .with_attribute("tool", tool.clone())
+                    .with_attribute("decision", decision),
+            );
+        }
+    }
+    for (model, usage) in &activity.models {
+        let token_types = [
+            ("input", usage.input_tokens),
+            ("output", usage.output_tokens),
+            ("cache_read", usage.cache_read_tokens),
+            ("cache_creation", usage.cache_creation_tokens),
+        ];
+        for (token_type, tokens) in token_types {
+            samples.push(
+                actor(Sample::counter(&ANALYTICS_TOKENS, tokens))
+                    .with_attribute("model", model.clone())
+                    .with_attribute("type", token_type),
+            );
+        }
+        if !usage.currency.is_empty() {
+            samples.push(
+                actor(Sample::counter_f64(&ANALYTICS_COST, usage.estimated_cost))
+                    .with_attribute("model", model.clone())
+                    .with_attribute("currency", usage.currency.clone()),
+            );
+        }
+    }
+    samples
+}
+
+pub struct ClaudeCodeAnalyticsCollector {
+    config: ClaudeCodeAnalyticsConfig,
+    client: AdminClient,
+    store: StateStore,
+    /// Totals already reported per day (`YYYY-MM-DD`) and series. Loaded
+    /// from the state store on the first run.
+    reported: Mutex<Option<BTreeMap<String, BTreeMap<String, f64>>>>,
+}
+
+impl ClaudeCodeAnalyticsCollector {
+    pub fn new(
+        config: ClaudeCodeAnalyticsConfig,
+        store: StateStore,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Self> {
+        let client = AdminClient::new(config.base_url.clone(), config.timeout)?.with_retry(retry);
+        Ok(Self {
+            config,
+            client,
+            store,
+            reported: Mutex::default(),
+        })
+    }
+
+    /// Counter increments for the activity of each day, forgetting days that
+    /// are no longer fetched.
+    fn increments(
+        &self,
+        days: Vec<(NaiveDate, Vec<ClaudeCodeActivity>)>,
+    ) -> anyhow::Result<Vec<Sample>> {
+        let mut reported = self.reported.lock().unwrap();
+        let reported = reported.get_or_insert_with(|| self.store.load(ANALYTICS_STATE_NAME));
+        let dates: Vec<String> = days.iter().map(|(date, _)| date.to_string()).collect();
+        reported.retain(|date, _| dates.contains(date));
+
+        let mut samples = Vec::new();
+        for ((_, activity), date) in days.into_iter().zip(dates) {
+            let day = reported.entry(date).or_default();
+            for activity in &activity {
+                samples.extend(into_increments(activity_samples(activity), day));
+            }
+        }
+        self.store.save(ANALYTICS_STATE_NAME, &*reported)?;
+        Ok(samples)
+    }
+}
+
+#[async_trait]
+impl Collector for ClaudeCodeAnalyticsCollector {
+    fn name(&self) -> &'static str {
+        "claude_code_analytics"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Code Analytics"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "claude-code-analytics"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "claude_code_analytics_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Fetching Claude Code usage report");
+
+        let api_key = std::env::var(&self.config.admin_key_env).with_context(|| {
+            format!("{} environment variable not set", self.config.admin_key_env)
+        })?;
+
+        // Yesterday is fetched again because its report keeps filling in for
+        // a while after midnight UTC.
+        let today = Utc::now().date_naive();
+        let dates = [today - chrono::Days::new(1), today];
+        let reports = futures_util::future::try_join_all(
+            dates.map(|date| self.client.fetch_claude_code_activity(&api_key, date)),
+        )
+        .await?;
+
+        let actors = reports.last().map_or(0, Vec::len);
+        let samples = self.increments(dates.into_iter().zip(reports).collect())?;
+
+        info!(actors, "Fetched Claude Code usage report");
+
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::Value;
+    use std::io::{Read, Write};
+
+    /// Serves one canned JSON body per connection, in order, and returns the
+    /// base URL together with the request lines received.
+    fn serve(bodies: &'static [&'static str]) -> (String, std::sync::mpsc::Receiver<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (requests, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 4096];
+                let read = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                requests
+                    .send(request.lines().next().unwrap_or_default().to_string())
+                    .unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://{addr}/"), received)
+    }
+
+    #[tokio::test]
+    async fn test_usage_report_follows_pages_and_sums_buckets() {
+        let (base_url, requests) = serve(&[
+            r#"{"data":[{"starting_at":"2026-01-01T00:00:00Z","results":[{"model":"claude-sonnet-4","workspace_id":null,"api_key_id":"apikey_1","uncached_input_tokens":100,"cache_creation":{"ephemeral_5m_input_tokens":10,"ephemeral_1h_input_tokens":1},"cache_read_input_tokens":50,"output_tokens":20}]}],"has_more":true,"next_page":"page_2"}"#,
+            r#"{"data":[{"starting_at":"2026-01-02T00:00:00Z","results":[{"model":"claude-sonnet-4","workspace_id":null,"api_key_id":"apikey_1","uncached_input_tokens":5,"cache_creation":null,"cache_read_input_tokens":0,"output_tokens":2}]}],"has_more":false,"next_page":null}"#,
+        ]);
+        let client = AdminClient::new(base_url, Duration::from_secs(5)).unwrap();
+        let starting_at = month_start("2026-01-15T12:00:00Z".parse().unwrap());
+
+        let usage = client
+            .fetch_usage("sk-ant-admin", starting_at)
+            .await
+            .unwrap();
+        assert_eq!(
+            usage,
+            vec![ApiTokenUsage {
+                model: "claude-sonnet-4".to_string(),
+                workspace_id: String::new(),
+                api_key_id: "apikey_1".to_string(),
+                uncached_input_tokens: 105,
+                cache_creation_5m_input_tokens: 10,
+                cache_creation_1h_input_tokens: 1,
+                cache_read_input_tokens: 50,
+                output_tokens: 22,
+            }]
+        );
+
+        let first = requests.recv().unwrap();
+        assert!(first.starts_with("GET /v1/organizations/usage_report/messages?"));
+        assert!(first.contains("starting_at=2026-01-01T00%3A00%3A00Z"));
+        assert!(requests.recv().unwrap().contains("page=page_2"));
+    }
+
+    #[tokio::test]
+    async fn test_past_month_usage_ends_at_the_next_month() {
+        let (base_url, requests) = serve(&[r#"{"data":[],"has_more":false,"next_page":null}"#]);
+        let client = AdminClient::new(base_url, Duration::from_secs(5)).unwrap();
+        let january = month_start("2026-01-20T00:00:00Z".parse().unwrap());
+        let february = month_start("2026-02-01T00:05:00Z".parse().unwrap());
+
+        let usage = client
+            .fetch_usage_between("sk-ant-admin", january, february)
+            .await
+            .unwrap();
+        assert!(usage.is_empty());
+        let request = requests.recv().unwrap();
+        assert!(request.contains("starting_at=2026-01-01T00%3A00%3A00Z"));
+        assert!(request.contains("ending_at=2026-02-01T00%3A00%3A00Z"));
+    }
+
+    #[test]
+    fn test_costs_are_converted_from_cents_and_summed() {
+        let results: Vec<CostResult> = serde_json::from_str(
+            r#"[
+                {"currency":"USD","amount":"123.45","workspace_id":"wrkspc_1","description":"Claude Sonnet 4 Usage - Input Tokens","cost_type":"tokens","model":"claude-sonnet-4","token_type":"uncached_input_tokens"},
+                {"currency":"USD","amount":"100","workspace_id":"wrkspc_1","description":"Claude Sonnet 4 Usage - Input Tokens","cost_type":"tokens","model":"claude-sonnet-4","token_type":"uncached_input_tokens"},
+                {"currency":"USD","amount":"50","workspace_id":null,"description":"Web Search","cost_type":"web_search","model":null,"token_type":null}
+            ]"#,
+        )
+        .unwrap();
+        let costs = sum_costs(results).unwrap();
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].workspace_id, "");
+        assert_eq!(costs[0].cost_type, "web_search");
+        assert_eq!(costs[0].amount, 0.5);
+        assert_eq!(costs[1].model, "claude-sonnet-4");
+        assert!((costs[1].amount - 2.2345).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_claude_code_records_are_summed_per_known_actor() {
+        let page: ReportPage<ClaudeCodeRecord> = serde_json::from_str(
+            r#"{"data":[
+                {"date":"2026-01-05T00:00:00Z","actor":{"type":"user_actor","email_address":"dev@example.com"},"organization_id":"org","customer_type":"api","terminal_type":"vscode",
+                 "core_metrics":{"num_sessions":2,"lines_of_code":{"added":100,"removed":20},"commits_by_claude_code":1,"pull_requests_by_claude_code":0},
+                 "tool_actions":{"edit_tool":{"accepted":10,"rejected":2}},
+                 "model_breakdown":[{"model":"claude-sonnet-4","tokens":{"input":1000,"output":500,"cache_read":0,"cache_creation":10},"estimated_cost":{"currency":"USD","amount":186}}]},
+                {"date":"2026-01-05T00:00:00Z","actor":{"type":"user_actor","email_address":"dev@example.com"},"organization_id":"org","customer_type":"api","terminal_type":"iTerm.app",
+                 "core_metrics":{"num_sessions":1,"lines_of_code":{"added":5,"removed":0},"commits_by_claude_code":0,"pull_requests_by_claude_code":1},
+                 "tool_actions":{"edit_tool":{"accepted":1,"rejected":0}},
+                 "model_breakdown":[{"model":"claude-sonnet-4","tokens":{"input":1,"output":1,"cache_read":0,"cache_creation":0},"estimated_cost":{"currency":"USD","amount":14}}]},
+                {"date":"2026-01-05T00:00:00Z","actor":{"type":"api_actor","api_key_name":"ci"},"organization_id":"org","customer_type":"api","terminal_type":"non-interactive",
+                 "core_metrics":{"num_sessions":4},"tool_actions":{},"model_breakdown":[]},
+                {"date":"2026-01-05T00:00:00Z","actor":{"type":"service_actor","service_name":"bot"},"organization_id":"org","customer_type":"api","terminal_type":"non-interactive",
+                 "core_metrics":{"num_sessions":7},"tool_actions":{},"model_breakdown":[]}
+            ],"has_more":false,"next_page":null}"#,
+        )
+        .unwrap();
+
+        let activity = sum_activity(page.data);
+        assert_eq!(activity.len(), 2);
+        assert_eq!(
+            (activity[0].actor_type, activity[0].sessions),
+            ("api_key", 4)
+        );
+        let user = &activity[1];
+        assert_eq!(user.actor, "dev@example.com");
+        assert_eq!(user.sessions, 3);
+        assert_eq!(user.lines_added, 105);
+        assert_eq!(user.pull_requests, 1);
+        assert_eq!(user.tool_actions["edit_tool"].accepted, 11);
+        let model = &user.models["claude-sonnet-4"];
+        assert_eq!(model.input_tokens, 1001);
+        assert_eq!(model.estimated_cost, 2.0);
+    }
+
+    #[test]
+    fn test_daily_totals_become_increments() {
+        let collector = ClaudeCodeAnalyticsCollector::new(
+            ClaudeCodeAnalyticsConfig::default(),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let activity = |sessions| {
+            vec![ClaudeCodeActivity {
+                actor: "dev@example.com".to_string(),
+                actor_type: "user",
+                sessions,
+                ..ClaudeCodeActivity::default()
+            }]
+        };
+        let sessions = |samples: Vec<Sample>| -> Vec<Value> {
+            samples
+                .into_iter()
+                .filter(|s| s.descriptor.name == "claude_code.analytics.sessions")
+                .map(|s| s.value)
+                .collect()
+        };
+        let day = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+
+        let samples = collector
+            .increments(vec![(day(4), activity(5)), (day(5), activity(1))])
+            .unwrap();
+        assert_eq!(
+            sessions(samples),
+            vec![Value::Counter(5), Value::Counter(1)]
+        );
+
+        // A late session on the 4th and a new one on the 5th.
+        let samples = collector
+            .increments(vec![(day(4), activity(6)), (day(5), activity(2))])
+            .unwrap();
+        assert_eq!(
+            sessions(samples),
+            vec![Value::Counter(1), Value::Counter(1)]
+        );
+
+        let samples = collector
+            .increments(vec![(day(5), activity(2)), (day(6), activity(3))])
+            .unwrap();
+        assert_eq!(
+            sessions(samples),
+            vec![Value::Counter(0), Value::Counter(3)]
+        );
+        assert_eq!(
+            collector.reported.lock().unwrap().as_ref().unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_token_increments_restart_each_month() {
+        let collector = ClaudeApiCollector::new(
+            ClaudeApiConfig::default(),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let usage = |output_tokens| {
+            vec![ApiTokenUsage {
+                model: "claude-opus-4".to_string(),
+                output_tokens,
+                ..ApiTokenUsage::default()
+            }]
+        };
+        let output = |samples: Vec<Sample>| {
+            samples
+                .into_iter()
+                .find(|s| {
+                    s.attributes
+                        .contains(&("type".to_string(), "output".to_string()))
+                })
+                .unwrap()
+                .value
+        };
+        let january = month_start("2026-01-20T00:00:00Z".parse().unwrap());
+        let february = month_start("2026-02-01T00:05:00Z".parse().unwrap());
+
+        let samples = collector.token_samples(january, &usage(10)).unwrap();
+        assert_eq!(output(samples), Value::Counter(10));
+        let samples = collector.token_samples(january, &usage(25)).unwrap();
+        assert_eq!(output(samples), Value::Counter(15));
+        assert_eq!(collector.unfinished_month(january), None);
+
+        // January's report grew after its last run; it is completed first.
+        assert_eq!(collector.unfinished_month(february), Some(january));
+        let samples = collector.token_samples(january, &usage(30)).unwrap();
+        assert_eq!(output(samples), Value::Counter(5));
+        let samples = collector.token_samples(february, &usage(4)).unwrap();
+        assert_eq!(output(samples), Value::Counter(4));
+        assert_eq!(collector.unfinished_month(february), None);
+    }
+}
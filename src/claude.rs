@@ -0,0 +1,1648 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::collector::{Collector, Descriptor, ErrorClass, Sample};
+use crate::config::{ClaudeAuth, ClaudeConfig};
+use crate::cookiejar::{self, CookieJar};
+use crate::pace::{PaceTracker, RESET_TOLERANCE};
+use crate::retry::{self, RetryPolicy};
+use crate::state::StateStore;
+
+pub mod oauth;
+
+use oauth::{ClaudeOAuthClient, OAuthEndpoints};
+
+// ============================================================================
+// Claude Types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct UsageInfo {
+    utilization: Option<f64>,
+    resets_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageResponse {
+    five_hour: Option<UsageInfo>,
+    seven_day: Option<UsageInfo>,
+    seven_day_oauth_apps: Option<UsageInfo>,
+    seven_day_opus: Option<UsageInfo>,
+    seven_day_sonnet: Option<UsageInfo>,
+    seven_day_cowork: Option<UsageInfo>,
+    iguana_necktie: Option<UsageInfo>,
+    extra_usage: Option<ExtraUsageResponse>,
+    /// Top-level keys this version does not know by name.
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+/// `extra_usage` carries the usual window fields plus billing details, with
+/// amounts in the minor unit of `currency` (cents for USD, yen for JPY).
+#[derive(Debug, Deserialize)]
+struct ExtraUsageResponse {
+    #[serde(flatten)]
+    window: UsageInfo,
+    is_enabled: Option<bool>,
+    monthly_limit: Option<f64>,
+    used_credits: Option<f64>,
+    currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrganizationResponse {
+    uuid: String,
+    name: String,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+/// A Claude organization the session belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+}
+
+/// Usage of a single Claude limit window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageMetric {
+    /// Window identifier as returned by the API (e.g. `five_hour`).
+    pub name: String,
+    /// Percentage of the limit consumed, from 0 to 100.
+    pub utilization: f64,
+    pub seconds_to_reset: Option<i64>,
+    /// When the window ends, if it has started.
+    pub resets_at: Option<DateTime<Utc>>,
+    /// Whether the window is one this version knows by name. Other windows
+    /// are picked up from their `{utilization, resets_at}` shape.
+    pub recognized: bool,
+}
+
+/// Usage billed beyond the plan limits, in major currency units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtraUsage {
+    pub enabled: bool,
+    /// Monthly spend cap, or `None` when no cap is set.
+    pub monthly_limit: Option<f64>,
+    pub used_credits: Option<f64>,
+    /// ISO 4217 code; the API omits it for USD accounts.
+    pub currency: String,
+}
+
+impl From<&ExtraUsageResponse> for ExtraUsage {
+    /// The API reports amounts in the currency's minor unit, e.g. cents for
+    /// USD but whole yen for JPY.
+    fn from(response: &ExtraUsageResponse) -> Self {
+        let currency = response
+            .currency
+            .clone()
+            .unwrap_or_else(|| "USD".to_string());
+        let scale = 10f64.powi(minor_unit_exponent(&currency));
+        Self {
+            enabled: response.is_enabled.unwrap_or(false),
+            monthly_limit: response.monthly_limit.map(|amount| amount / scale),
+            used_credits: response.used_credits.map(|amount| amount / scale),
+            currency,
+        }
+    }
+}
+
+/// Decimal places of the ISO 4217 `currency`'s minor unit; two unless the
+/// currency is listed with none, three or four.
+fn minor_unit_exponent(currency: &str) -> i32 {
+    match currency.to_ascii_uppercase().as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    }
+}
+
+/// Point-in-time view of a Claude organization's usage limits.
+#[derive(Debug, Clone)]
+pub struct ClaudeUsageSnapshot {
+    pub organization_id: String,
+    pub fetched_at: DateTime<Utc>,
+    pub metrics: Vec<UsageMetric>,
+    pub extra_usage: Option<ExtraUsage>,
+}
+
+impl From<UsageResponse> for Vec<UsageMetric> {
+    fn from(response: UsageResponse) -> Self {
+        let now = Utc::now();
+        let known: [(&str, Option<UsageInfo>); 8] = [
+            ("five_hour", response.five_hour),
+            ("seven_day", response.seven_day),
+            ("seven_day_oauth_apps", response.seven_day_oauth_apps),
+            ("seven_day_opus", response.seven_day_opus),
+            ("seven_day_sonnet", response.seven_day_sonnet),
+            ("seven_day_cowork", response.seven_day_cowork),
+            ("iguana_necktie", response.iguana_necktie),
+            (
+                "extra_usage",
+                response.extra_usage.map(|extra| extra.window),
+            ),
+        ];
+        let known = known
+            .into_iter()
+            .map(|(name, info)| (name.to_string(), info, true));
+        let discovered = response
+            .other
+            .into_iter()
+            .map(|(name, value)| (name, usage_window(value), false));
+
+        known
+            .chain(discovered)
+            .filter_map(|(name, info, recognized)| {
+                info.and_then(|i| {
+                    i.utilization.map(|utilization| {
+                        let resets_at = i.resets_at.and_then(|reset_str| {
+                            DateTime::parse_from_rfc3339(&reset_str)
+                                .ok()
+                                .map(|reset_time| reset_time.with_timezone(&Utc))
+                        });
+                        let seconds_to_reset =
+                            resets_at.map(|reset_time| (reset_time - now).num_seconds().max(0));
+                        UsageMetric {
+                            name,
+                            utilization,
+                            seconds_to_reset,
+                            resets_at,
+                            recognized,
+                        }
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+fn chat_organizations(organizations: Vec<OrganizationResponse>) -> Vec<Organization> {
+    organizations
+        .into_iter()
+        .filter(|org| org.capabilities.is_empty() || org.capabilities.iter().any(|c| c == "chat"))
+        .map(|org| Organization {
+            id: org.uuid,
+            name: org.name,
+        })
+        .collect()
+}
+
+/// Reads an unknown top-level value as a usage window if it has the
+/// `{utilization, resets_at}` shape; `resets_at` may be null but not missing.
+fn usage_window(value: serde_json::Value) -> Option<UsageInfo> {
+    let is_window = value.as_object().is_some_and(|object| {
+        object.contains_key("utilization") && object.contains_key("resets_at")
+    });
+    if !is_window {
+        return None;
+    }
+    serde_json::from_value(value).ok()
+}
+
+// ============================================================================
+// Claude Errors
+// ============================================================================
+
+/// Failures that call for a specific remedy rather than a retry.
+#[derive(Debug)]
+pub enum ClaudeError {
+    /// The session cookie was rejected (HTTP 401/403); log in to claude.ai
+    /// again so cookiejar picks up a fresh session.
+    SessionExpired(reqwest::Error),
+    /// Cloudflare answered with a bot challenge or block page instead of JSON.
+    CloudflareChallenge,
+    /// The organization does not exist or the session cannot see it.
+    OrganizationNotFound(String),
+    /// The response was JSON but not in the expected shape.
+    Schema(serde_json::Error),
+}
+
+impl ClaudeError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Self::SessionExpired(_) => ErrorClass::Auth,
+            Self::CloudflareChallenge => ErrorClass::Challenge,
+            Self::OrganizationNotFound(_) => ErrorClass::NotFound,
+            Self::Schema(_) => ErrorClass::Parse,
+        }
+    }
+
+    /// Classifies a non-2xx response, or returns `None` to fall back to the
+    /// generic status handling.
+    fn from_response(response: &reqwest::Response, organization_id: Option<&str>) -> Option<Self> {
+        let status = response.status();
+        let headers = response.headers();
+        let challenged = headers
+            .get("cf-mitigated")
+            .is_some_and(|value| value == "challenge");
+        let html = headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/html"));
+
+        match status.as_u16() {
+            _ if challenged => Some(Self::CloudflareChallenge),
+            403 if html => Some(Self::CloudflareChallenge),
+            401 | 403 => response
+                .error_for_status_ref()
+                .err()
+                .map(Self::SessionExpired),
+            404 => organization_id.map(|id| Self::OrganizationNotFound(id.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ClaudeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SessionExpired(e) => write!(
+                f,
+                "Claude session was rejected ({}); log in to claude.ai again",
+                e.status().map(|s| s.to_string()).unwrap_or_default()
+            ),
+            Self::CloudflareChallenge => {
+                f.write_str("Claude API request was blocked by a Cloudflare challenge")
+            }
+            Self::OrganizationNotFound(id) => write!(
+                f,
+                "Claude organization {id} was not found or is not accessible with this session"
+            ),
+            Self::Schema(_) => f.write_str("Claude API response did not match the expected schema"),
+        }
+    }
+}
+
+impl std::error::Error for ClaudeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SessionExpired(e) => Some(e),
+            Self::Schema(e) => Some(e),
+            Self::CloudflareChallenge | Self::OrganizationNotFound(_) => None,
+        }
+    }
+}
+
+/// Parses an API response body, telling HTML interstitials apart from schema
+/// changes.
+fn parse_body<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, ClaudeError> {
+    serde_json::from_str(body).map_err(|e| {
+        if body.trim_start().starts_with('<') {
+            ClaudeError::CloudflareChallenge
+        } else {
+            ClaudeError::Schema(e)
+        }
+    })
+}
+
+// ============================================================================
+// Claude Client
+// ============================================================================
+
+/// Where [`ClaudeClient`] finds the claude.ai API and its session cookies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaudeEndpoints {
+    pub base_url: String,
+    pub cookie_host: String,
+    pub organizations_path: String,
+    /// `{organization_id}` is replaced with the organization ID.
+    pub usage_path: String,
+}
+
+impl ClaudeEndpoints {
+    pub fn from_config(config: &ClaudeConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            cookie_host: config
+                .cookie_host()
+                .context("collectors.claude.cookie_host is required when base_url has no host")?,
+            organizations_path: config.organizations_path.clone(),
+            usage_path: config.usage_path.clone(),
+        })
+    }
+
+    fn organizations_url(&self) -> String {
+        format!("{}{}", self.base_url, self.organizations_path)
+    }
+
+    fn usage_url(&self, organization_id: &str) -> String {
+        let path = self
+            .usage_path
+            .replace("{organization_id}", organization_id);
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+impl Default for ClaudeEndpoints {
+    /// claude.ai itself.
+    fn default() -> Self {
+        Self::from_config(&ClaudeConfig::default()).expect("claude.ai has a host")
+    }
+}
+
+/// Fetches Claude usage with browser cookies borrowed from cookiejar-reader.
+#[derive(Debug, Clone)]
+pub struct ClaudeClient {
+    cookiejar: CookieJar,
+    http_client: reqwest::Client,
+    endpoints: ClaudeEndpoints,
+    retry: RetryPolicy,
+}
+
+impl ClaudeClient {
+    pub fn new(cookiejar: CookieJar, timeout: Duration) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            cookiejar,
+            http_client,
+            endpoints: ClaudeEndpoints::default(),
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Points the client at another deployment than claude.ai.
+    pub fn with_endpoints(mut self, endpoints: ClaudeEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Replaces the default retry policy for API requests.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Lists the organizations the session can read usage for. API-only
+    /// organizations have no chat usage limits and are left out.
+    pub async fn fetch_organizations(&self) -> anyhow::Result<Vec<Organization>> {
+        let url = self.endpoints.organizations_url();
+        let body = self.get(&url, "claude.organizations", None).await?;
+        let organizations = parse_body::<Vec<OrganizationResponse>>(&body)
+            .with_context(|| format!("Failed to parse organizations response: {}", body))?;
+
+        Ok(chat_organizations(organizations))
+    }
+
+    pub async fn fetch_usage(&self, organization_id: &str) -> anyhow::Result<ClaudeUsageSnapshot> {
+        let url = self.endpoints.usage_url(organization_id);
+        let body = self
+            .get(&url, "claude.usage", Some(organization_id))
+            .await?;
+        let usage_response = parse_body::<UsageResponse>(&body)
+            .with_context(|| format!("Failed to parse usage response: {}", body))?;
+
+        Ok(ClaudeUsageSnapshot {
+            organization_id: organization_id.to_string(),
+            fetched_at: Utc::now(),
+            extra_usage: usage_response.extra_usage.as_ref().map(ExtraUsage::from),
+            metrics: usage_response.into(),
+        })
+    }
+
+    /// GETs `url` with the session cookies and returns the response body.
+    /// A 404 is reported as [`ClaudeError::OrganizationNotFound`] when the URL
+    /// belongs to `organization_id`.
+    async fn get(
+        &self,
+        url: &str,
+        operation: &'static str,
+        organization_id: Option<&str>,
+    ) -> anyhow::Result<String> {
+        let cookies = self
+            .cookiejar
+            .fetch_cookies(&self.endpoints.cookie_host)
+            .await?;
+        self.retry
+            .run(operation, || async {
+                let response = self
+                    .http_client
+                    .get(url)
+                    .header("Cookie", &cookies)
+                    .header("User-Agent", cookiejar::BROWSER_USER_AGENT)
+                    .send()
+                    .await
+                    .context("Failed to send request to Claude API")?;
+                if !response.status().is_success()
+                    && let Some(error) = ClaudeError::from_response(&response, organization_id)
+                {
+                    return Err(error.into());
+                }
+                retry::check_status(response, "Claude API")?
+                    .text()
+                    .await
+                    .context("Failed to read response body")
+            })
+            .await
+    }
+}
+
+// ============================================================================
+// Claude Window Tracking
+// ============================================================================
+
+/// Name of the window tracking state in the state store.
+const WINDOWS_STATE_NAME: &str = "claude_windows";
+
+/// The current window of one organization's limit, as last observed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct WindowState {
+    /// `None` while the window has not started, e.g. an idle `five_hour`.
+    resets_at: Option<DateTime<Utc>>,
+    /// Highest utilization seen in this window, from 0 to 100.
+    peak: f64,
+    /// Peak of the window before this one, once one has closed.
+    previous_peak: Option<f64>,
+}
+
+/// A window that closed between two observations.
+#[derive(Debug, Clone, PartialEq)]
+struct ClosedWindow {
+    resets_at: DateTime<Utc>,
+    /// Highest utilization seen in the window, from 0 to 100.
+    peak: f64,
+}
+
+/// Windows by organization ID and window name, so resets are noticed across
+/// restarts. When the collector was down over several resets, they count as
+/// one, with the peak seen before the gap.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WindowTracker {
+    windows: BTreeMap<String, WindowState>,
+}
+
+impl WindowTracker {
+    /// Folds `metric` into its window and returns the window it closed, if
+    /// any. A window closes when `resets_at` moves forward, or when it
+    /// disappears after its reset time has passed.
+    fn observe(
+        &mut self,
+        organization_id: &str,
+        metric: &UsageMetric,
+        now: DateTime<Utc>,
+    ) -> Option<ClosedWindow> {
+        let window = self
+            .windows
+            .entry(format!("{organization_id}/{}", metric.name))
+            .or_insert_with(|| WindowState {
+                resets_at: metric.resets_at,
+                peak: metric.utilization,
+                previous_peak: None,
+            });
+
+        let closed = match (window.resets_at, metric.resets_at) {
+            (Some(previous), Some(current)) if current > previous + RESET_TOLERANCE => {
+                Some(previous)
+            }
+            (Some(previous), None) if previous <= now => Some(previous),
+            _ => None,
+        };
+        let Some(resets_at) = closed else {
+            window.resets_at = window.resets_at.or(metric.resets_at);
+            window.peak = window.peak.max(metric.utilization);
+            return None;
+        };
+
+        let closed = ClosedWindow {
+            resets_at,
+            peak: window.peak,
+        };
+        *window = WindowState {
+            resets_at: metric.resets_at,
+            peak: metric.utilization,
+            previous_peak: Some(closed.peak),
+        };
+        Some(closed)
+    }
+
+    fn previous_peak(&self, organization_id: &str, metric: &UsageMetric) -> Option<f64> {
+        self.windows
+            .get(&format!("{organization_id}/{}", metric.name))
+            .and_then(|window| window.previous_peak)
+    }
+}
+
+/// Length of the windows whose pace can be derived from their reset time.
+fn window_length(name: &str) -> Option<TimeDelta> {
+    match name {
+        "five_hour" => Some(TimeDelta::hours(5)),
+        "seven_day"
+        | "seven_day_oauth_apps"
+        | "seven_day_opus"
+        | "seven_day_sonnet"
+        | "seven_day_cowork" => Some(TimeDelta::days(7)),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Claude Metrics Collection
+// ============================================================================
+
+static UTILIZATION: Descriptor = Descriptor {
+    name: "claude.usage.utilization",
+    description: "Current Claude usage utilization rate",
+    unit: "ratio",
+};
+
+static SECONDS_TO_RESET: Descriptor = Descriptor {
+    name: "claude.usage.seconds_to_reset",
+    description: "Seconds until usage limit resets",
+    unit: "s",
+};
+
+static RESETS: Descriptor = Descriptor {
+    name: "claude.usage.resets",
+    description: "Usage windows that reset since the collector started tracking them",
+    unit: "{reset}",
+};
+
+static PREVIOUS_WINDOW_PEAK: Descriptor = Descriptor {
+    name: "claude.usage.previous_window_peak_utilization",
+    description: "Highest utilization observed in the usage window that closed last",
+    unit: "ratio",
+};
+
+static PACE: Descriptor = Descriptor {
+    name: "claude.usage.pace",
+    description: "Utilization divided by the elapsed fraction of the usage window",
+    unit: "1",
+};
+
+static PROJECTED_UTILIZATION: Descriptor = Descriptor {
+    name: "claude.usage.projected_utilization",
+    description: "Utilization expected when the usage window resets, at the recent rate",
+    unit: "ratio",
+};
+
+static UNRECOGNIZED_WINDOW: Descriptor = Descriptor {
+    name: "claude.usage.unrecognized_window",
+    description: "Set to 1 for usage windows discovered by shape rather than by name",
+    unit: "1",
+};
+
+static EXTRA_USAGE_ENABLED: Descriptor = Descriptor {
+    name: "claude.extra_usage.enabled",
+    description: "Whether usage beyond the plan limits is billed (1) or blocked (0)",
+    unit: "1",
+};
+
+static EXTRA_USAGE_MONTHLY_LIMIT: Descriptor = Descriptor {
+    name: "claude.extra_usage.monthly_limit",
+    description: "Monthly spend cap for extra usage, in the `currency` attribute's unit",
+    unit: "{currency}",
+};
+
+static EXTRA_USAGE_USED_CREDITS: Descriptor = Descriptor {
+    name: "claude.extra_usage.used_credits",
+    description: "Extra usage spent this month, in the `currency` attribute's unit",
+    unit: "{currency}",
+};
+
+static SESSION_VALID: Descriptor = Descriptor {
+    name: "claude.session.valid",
+    description: "Whether the organization's usage was readable (1) or the session was rejected (0) on the last run",
+    unit: "1",
+};
+
+/// Client for the configured `auth` mode.
+enum ClaudeApi {
+    Cookies(ClaudeClient),
+    Oauth(ClaudeOAuthClient),
+}
+
+pub struct ClaudeCollector {
+    config: ClaudeConfig,
+    /// `None` when the collector is disabled and its client could not be
+    /// built, e.g. without a credentials file.
+    api: Option<ClaudeApi>,
+    /// Organization of the OAuth credentials, once looked up.
+    oauth_organization: Mutex<Option<Organization>>,
+    /// Organizations whose session was rejected in a run that failed, for
+    /// [`Collector::failure_samples`].
+    expired_sessions: Mutex<Vec<Organization>>,
+    /// Organization names from the last successful listing, by ID.
+    organization_names: Mutex<BTreeMap<String, String>>,
+    /// Unrecognized windows already reported, so each is logged once.
+    seen_windows: Mutex<BTreeSet<String>>,
+    store: StateStore,
+    /// Loaded from the state store on the first run.
+    windows: Mutex<Option<WindowTracker>>,
+    pace: Mutex<PaceTracker>,
+}
+
+impl ClaudeCollector {
+    pub fn new(
+        config: ClaudeConfig,
+        cookiejar: CookieJar,
+        store: StateStore,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Self> {
+        let api = match api(&config, cookiejar, retry) {
+            Ok(api) => Some(api),
+            // A disabled collector never runs, so an incomplete configuration
+            // must not stop the others.
+            Err(e) if !config.enabled => {
+                debug!(error = %e, "Skipping the client of the disabled Claude collector");
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            config,
+            api,
+            oauth_organization: Mutex::default(),
+            expired_sessions: Mutex::default(),
+            organization_names: Mutex::default(),
+            seen_windows: Mutex::default(),
+            store,
+            windows: Mutex::default(),
+            pace: Mutex::default(),
+        })
+    }
+
+    /// Resolves the organizations to collect: the configured IDs followed by
+    /// any discovered ones. Names of configured IDs are looked up on a best
+    /// effort basis and fall back to the ID.
+    async fn organizations(&self, client: &ClaudeClient) -> anyhow::Result<Vec<Organization>> {
+        let configured = self.config.organization_ids();
+        let needs_listing = self.config.discover_organizations || {
+            let names = self.organization_names.lock().unwrap();
+            configured.iter().any(|id| !names.contains_key(id))
+        };
+
+        let mut discovered = Vec::new();
+        if needs_listing {
+            match client.fetch_organizations().await {
+                Ok(organizations) => {
+                    let mut names = self.organization_names.lock().unwrap();
+                    for org in &organizations {
+                        names.insert(org.id.clone(), org.name.clone());
+                    }
+                    discovered = organizations;
+                }
+                Err(e) if !self.config.discover_organizations => {
+                    warn!(error = %e, "Failed to look up Claude organization names");
+                }
+                Err(e) => return Err(e.context("Failed to discover Claude organizations")),
+            }
+        }
+
+        let mut organizations = self.configured_organizations();
+        if self.config.discover_organizations {
+            for org in discovered {
+                if !organizations.iter().any(|o| o.id == org.id) {
+                    organizations.push(org);
+                }
+            }
+        }
+        Ok(organizations)
+    }
+
+    /// The configured organization IDs, named after the last listing.
+    fn configured_organizations(&self) -> Vec<Organization> {
+        let names = self.organization_names.lock().unwrap();
+        self.config
+            .organization_ids()
+            .into_iter()
+            .map(|id| Organization {
+                name: names.get(&id).cloned().unwrap_or_else(|| id.clone()),
+                id,
+            })
+            .collect()
+    }
+
+    /// Collects every organization concurrently. A failing organization is
+    /// logged and skipped; the run only fails when none succeed.
+    async fn collect_cookies(&self, client: &ClaudeClient) -> anyhow::Result<Vec<Sample>> {
+        let organizations = self.organizations(client).await?;
+        if organizations.is_empty() {
+            anyhow::bail!("No Claude organizations to collect");
+        }
+
+        let fetches = organizations.iter().map(|org| client.fetch_usage(&org.id));
+        let results = futures_util::future::join_all(fetches).await;
+        self.organization_samples(&organizations, results)
+    }
+
+    /// Samples of every organization fetched, with `claude.session.valid` at
+    /// 0 for those whose session was rejected.
+    fn organization_samples(
+        &self,
+        organizations: &[Organization],
+        results: Vec<anyhow::Result<ClaudeUsageSnapshot>>,
+    ) -> anyhow::Result<Vec<Sample>> {
+        let mut samples = Vec::new();
+        let mut expired = Vec::new();
+        let mut succeeded = false;
+        let mut last_error = None;
+        for (org, result) in organizations.iter().zip(results) {
+            match result {
+                Ok(snapshot) => {
+                    succeeded = true;
+                    samples.extend(self.usage_samples(org, &snapshot));
+                }
+                Err(e) => {
+                    error!(
+                        organization_id = %org.id,
+                        error = %e,
+                        "Failed to fetch Claude usage for organization"
+                    );
+                    if is_session_expired(&e) {
+                        samples.push(expired_session_sample(org));
+                        expired.push(org.clone());
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !succeeded => {
+                *self.expired_sessions.lock().unwrap() = expired;
+                Err(e)
+            }
+            _ => Ok(samples),
+        }
+    }
+
+    /// Collects the single organization the OAuth credentials belong to.
+    /// Samples and usage windows are keyed by its ID, so the run fails until
+    /// the organization has been looked up once.
+    async fn collect_oauth(&self, oauth: &ClaudeOAuthClient) -> anyhow::Result<Vec<Sample>> {
+        let cached = self.oauth_organization.lock().unwrap().clone();
+        let org = match cached {
+            Some(org) => org,
+            None => {
+                let org = oauth
+                    .fetch_organization()
+                    .await
+                    .context("Failed to look up the Claude OAuth organization")?;
+                *self.oauth_organization.lock().unwrap() = Some(org.clone());
+                org
+            }
+        };
+        let snapshot = match oauth.fetch_usage().await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                if is_session_expired(&e) {
+                    *self.expired_sessions.lock().unwrap() = vec![org];
+                }
+                return Err(e);
+            }
+        };
+        Ok(self.usage_samples(&org, &snapshot))
+    }
+
+    fn usage_samples(&self, org: &Organization, snapshot: &ClaudeUsageSnapshot) -> Vec<Sample> {
+        let with_org = |sample: Sample| {
+            sample
+                .with_attribute("organization_id", org.id.clone())
+                .with_attribute("organization_name", org.name.clone())
+        };
+        let labelled = |sample: Sample, metric: &UsageMetric| {
+            with_org(sample.with_attribute("metric_name", metric.name.clone()))
+        };
+
+        let mut windows = self.windows.lock().unwrap();
+        let windows = windows.get_or_insert_with(|| self.store.load(WINDOWS_STATE_NAME));
+
+        let mut samples = Vec::new();
+        for metric in &snapshot.metrics {
+            samples.push(labelled(
+                Sample::f64(&UTILIZATION, metric.utilization / 100.0),
+                metric,
+            ));
+            if let Some(seconds) = metric.seconds_to_reset {
+                samples.push(labelled(Sample::i64(&SECONDS_TO_RESET, seconds), metric));
+            }
+            if let Some(resets_at) = metric.resets_at
+                && let Some(length) = window_length(&metric.name)
+                && let Some(pace) = self.pace.lock().unwrap().observe(
+                    &format!("{}/{}", org.id, metric.name),
+                    metric.utilization / 100.0,
+                    resets_at - length,
+                    resets_at,
+                    snapshot.fetched_at,
+                )
+            {
+                samples.push(labelled(Sample::f64(&PACE, pace.pace), metric));
+                samples.push(labelled(
+                    Sample::f64(&PROJECTED_UTILIZATION, pace.projected_utilization),
+                    metric,
+                ));
+            }
+            let closed = windows.observe(&org.id, metric, snapshot.fetched_at);
+            if let Some(closed) = &closed {
+                info!(
+                    organization_id = %org.id,
+                    metric_name = %metric.name,
+                    resets_at = %closed.resets_at,
+                    peak_utilization = %(closed.peak / 100.0),
+                    "Claude usage window reset"
+                );
+            }
+            samples.push(labelled(
+                Sample::counter(&RESETS, u64::from(closed.is_some())),
+                metric,
+            ));
+            if let Some(peak) = windows.previous_peak(&org.id, metric) {
+                samples.push(labelled(
+                    Sample::f64(&PREVIOUS_WINDOW_PEAK, peak / 100.0),
+                    metric,
+                ));
+            }
+            if !metric.recognized {
+                samples.push(labelled(Sample::i64(&UNRECOGNIZED_WINDOW, 1), metric));
+                if self
+                    .seen_windows
+                    .lock()
+                    .unwrap()
+                    .insert(metric.name.clone())
+                {
+                    warn!(
+                        metric_name = %metric.name,
+                        "Claude API returned a usage window this version does not recognize"
+                    );
+                }
+            }
+            info!(
+                organization_id = %org.id,
+                metric_name = %metric.name,
+                utilization = %(metric.utilization / 100.0),
+                seconds_to_reset = ?metric.seconds_to_reset,
+                "Fetched usage metric"
+            );
+        }
+
+        if let Some(extra) = &snapshot.extra_usage {
+            samples.push(with_org(Sample::i64(
+                &EXTRA_USAGE_ENABLED,
+                i64::from(extra.enabled),
+            )));
+            let amounts = [
+                (&EXTRA_USAGE_MONTHLY_LIMIT, extra.monthly_limit),
+                (&EXTRA_USAGE_USED_CREDITS, extra.used_credits),
+            ];
+            for (descriptor, amount) in amounts {
+                if let Some(amount) = amount {
+                    samples.push(with_org(
+                        Sample::f64(descriptor, amount)
+                            .with_attribute("currency", extra.currency.clone()),
+                    ));
+                }
+            }
+        }
+        samples.push(with_org(Sample::i64(&SESSION_VALID, 1)));
+        samples
+    }
+}
+
+/// Builds only the client of the configured `auth` mode, so settings of the
+/// other mode are never needed.
+fn api(
+    config: &ClaudeConfig,
+    cookiejar: CookieJar,
+    retry: RetryPolicy,
+) -> anyhow::Result<ClaudeApi> {
+    Ok(match config.auth {
+        ClaudeAuth::Cookies => ClaudeApi::Cookies(
+            ClaudeClient::new(cookiejar, config.timeout)?
+                .with_endpoints(ClaudeEndpoints::from_config(config)?)
+                .with_retry(retry),
+        ),
+        ClaudeAuth::Oauth => {
+            let path = config
+                .credentials_path()
+                .context("collectors.claude.credentials_path is not configured")?;
+            ClaudeApi::Oauth(
+                ClaudeOAuthClient::new(path, config.timeout)?
+                    .with_endpoints(OAuthEndpoints::from_config(config))
+                    .with_retry(retry),
+            )
+        }
+    })
+}
+
+fn is_session_expired(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ClaudeError>(),
+        Some(ClaudeError::SessionExpired(_))
+    )
+}
+
+fn expired_session_sample(org: &Organization) -> Sample {
+    Sample::i64(&SESSION_VALID, 0)
+        .with_attribute("organization_id", org.id.clone())
+        .with_attribute("organization_name", org.name.clone())
+}
+
+#[async_trait]
+impl Collector for ClaudeCollector {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "claude-usage-metrics"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "claude_usage_metrics_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Fetching Claude usage metrics");
+        self.expired_sessions.lock().unwrap().clear();
+
+        let samples = match &self.api {
+            Some(ClaudeApi::Cookies(client)) => self.collect_cookies(client).await?,
+            Some(ClaudeApi::Oauth(oauth)) => self.collect_oauth(oauth).await?,
+            None => anyhow::bail!("The Claude client is not configured"),
+        };
+
+        if let Some(windows) = &*self.windows.lock().unwrap()
+            && let Err(e) = self.store.save(WINDOWS_STATE_NAME, windows)
+        {
+            warn!(error = %e, "Failed to save Claude usage windows");
+        }
+        Ok(samples)
+    }
+
+    /// `claude.session.valid` at 0 for each organization whose session was
+    /// rejected. When it was rejected before any usage was fetched, the
+    /// configured or OAuth organizations stand in; without any, the failure
+    /// only shows in `collector.runs`.
+    fn failure_samples(&self, error: &anyhow::Error) -> Vec<Sample> {
+        let mut expired = std::mem::take(&mut *self.expired_sessions.lock().unwrap());
+        if expired.is_empty() && is_session_expired(error) {
+            expired = match self.config.auth {
+                ClaudeAuth::Oauth => self
+                    .oauth_organization
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .cloned()
+                    .collect(),
+                ClaudeAuth::Cookies => self.configured_organizations(),
+            };
+        }
+        expired.iter().map(expired_session_sample).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::Value;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_empty_response_returns_empty_vec() {
+        let response = UsageResponse {
+            five_hour: None,
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: None,
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert!(metrics.is_empty());
+    }
+
+    #[test]
+    fn test_single_field_with_no_reset_time() {
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.5),
+                resets_at: None,
+            }),
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: None,
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "five_hour");
+        assert_eq!(metrics[0].utilization, 0.5);
+        assert!(metrics[0].seconds_to_reset.is_none());
+    }
+
+    #[test]
+    fn test_single_field_with_future_reset_time() {
+        let future_time = Utc::now() + Duration::seconds(1800);
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.75),
+                resets_at: Some(future_time.to_rfc3339()),
+            }),
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: None,
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "five_hour");
+        assert_eq!(metrics[0].utilization, 0.75);
+        // Allow 1 second margin for test execution time
+        let seconds = metrics[0].seconds_to_reset.unwrap();
+        assert!((1799..=1800).contains(&seconds));
+    }
+
+    #[test]
+    fn test_past_reset_time_returns_zero() {
+        let past_time = Utc::now() - Duration::minutes(10);
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(1.0),
+                resets_at: Some(past_time.to_rfc3339()),
+            }),
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: None,
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics[0].seconds_to_reset, Some(0));
+    }
+
+    #[test]
+    fn test_invalid_reset_time_format_returns_none() {
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.5),
+                resets_at: Some("invalid-date-format".to_string()),
+            }),
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: None,
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 1);
+        assert!(metrics[0].seconds_to_reset.is_none());
+    }
+
+    #[test]
+    fn test_null_utilization_is_skipped() {
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.5),
+                resets_at: None,
+            }),
+            seven_day: None,
+            seven_day_oauth_apps: None,
+            seven_day_opus: None,
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: Some(ExtraUsageResponse {
+                window: UsageInfo {
+                    utilization: None,
+                    resets_at: None,
+                },
+                is_enabled: None,
+                monthly_limit: None,
+                used_credits: None,
+                currency: None,
+            }),
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "five_hour");
+    }
+
+    #[test]
+    fn test_multiple_fields_preserves_order() {
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.1),
+                resets_at: None,
+            }),
+            seven_day: Some(UsageInfo {
+                utilization: Some(0.2),
+                resets_at: None,
+            }),
+            seven_day_oauth_apps: None,
+            seven_day_opus: Some(UsageInfo {
+                utilization: Some(0.3),
+                resets_at: None,
+            }),
+            seven_day_sonnet: None,
+            seven_day_cowork: None,
+            iguana_necktie: None,
+            extra_usage: Some(ExtraUsageResponse {
+                window: UsageInfo {
+                    utilization: Some(0.4),
+                    resets_at: None,
+                },
+                is_enabled: None,
+                monthly_limit: None,
+                used_credits: None,
+                currency: None,
+            }),
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 4);
+        assert_eq!(metrics[0].name, "five_hour");
+        assert_eq!(metrics[0].utilization, 0.1);
+        assert_eq!(metrics[1].name, "seven_day");
+        assert_eq!(metrics[1].utilization, 0.2);
+        assert_eq!(metrics[2].name, "seven_day_opus");
+        assert_eq!(metrics[2].utilization, 0.3);
+        assert_eq!(metrics[3].name, "extra_usage");
+        assert_eq!(metrics[3].utilization, 0.4);
+    }
+
+    #[test]
+    fn test_all_fields_present() {
+        let response = UsageResponse {
+            five_hour: Some(UsageInfo {
+                utilization: Some(0.1),
+                resets_at: None,
+            }),
+            seven_day: Some(UsageInfo {
+                utilization: Some(0.2),
+                resets_at: None,
+            }),
+            seven_day_oauth_apps: Some(UsageInfo {
+                utilization: Some(0.3),
+                resets_at: None,
+            }),
+            seven_day_opus: Some(UsageInfo {
+                utilization: Some(0.4),
+                resets_at: None,
+            }),
+            seven_day_sonnet: Some(UsageInfo {
+                utilization: Some(0.5),
+                resets_at: None,
+            }),
+            seven_day_cowork: Some(UsageInfo {
+                utilization: Some(0.6),
+                resets_at: None,
+            }),
+            iguana_necktie: Some(UsageInfo {
+                utilization: Some(0.7),
+                resets_at: None,
+            }),
+            extra_usage: Some(ExtraUsageResponse {
+                window: UsageInfo {
+                    utilization: Some(0.8),
+                    resets_at: None,
+                },
+                is_enabled: None,
+                monthly_limit: None,
+                used_credits: None,
+                currency: None,
+            }),
+            other: BTreeMap::new(),
+        };
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics.len(), 8);
+        assert!(metrics.iter().all(|m| m.recognized));
+    }
+
+    #[test]
+    fn test_unknown_windows_are_discovered_by_shape() {
+        let response: UsageResponse = serde_json::from_str(
+            r#"{
+                "five_hour": {"utilization": 10.0, "resets_at": null},
+                "seven_day_haiku": {"utilization": 20.0, "resets_at": "2099-01-01T00:00:00Z"},
+                "another_window": {"utilization": 30.0, "resets_at": null},
+                "model_scores": {"utilization": 0.5, "rank": 2},
+                "some_flag": true,
+                "settings": {"enabled": true}
+            }"#,
+        )
+        .unwrap();
+        let metrics: Vec<UsageMetric> = response.into();
+        let names: Vec<(&str, bool)> = metrics
+            .iter()
+            .map(|m| (m.name.as_str(), m.recognized))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("five_hour", true),
+                ("another_window", false),
+                ("seven_day_haiku", false),
+            ]
+        );
+        assert!(metrics[2].seconds_to_reset.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_organization_listing_skips_api_only_orgs() {
+        let organizations: Vec<OrganizationResponse> = serde_json::from_str(
+            r#"[
+                {"uuid": "personal", "name": "Personal", "capabilities": ["chat", "claude_max"]},
+                {"uuid": "console", "name": "Console", "capabilities": ["api"]},
+                {"uuid": "team", "name": "Team", "settings": {}}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            chat_organizations(organizations),
+            vec![
+                Organization {
+                    id: "personal".to_string(),
+                    name: "Personal".to_string(),
+                },
+                Organization {
+                    id: "team".to_string(),
+                    name: "Team".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_usage_amounts_are_converted_from_cents() {
+        let response: UsageResponse = serde_json::from_str(
+            r#"{
+                "five_hour": {"utilization": 10.0, "resets_at": null},
+                "extra_usage": {
+                    "is_enabled": true,
+                    "monthly_limit": 5000,
+                    "used_credits": 1234,
+                    "utilization": 24.68
+                }
+            }"#,
+        )
+        .unwrap();
+        let extra = response.extra_usage.as_ref().map(ExtraUsage::from).unwrap();
+        assert_eq!(
+            extra,
+            ExtraUsage {
+                enabled: true,
+                monthly_limit: Some(50.0),
+                used_credits: Some(12.34),
+                currency: "USD".to_string(),
+            }
+        );
+
+        let metrics: Vec<UsageMetric> = response.into();
+        assert_eq!(metrics[1].name, "extra_usage");
+        assert_eq!(metrics[1].utilization, 24.68);
+    }
+
+    #[test]
+    fn test_extra_usage_amounts_follow_currency_minor_unit() {
+        let extra = |currency: &str| -> ExtraUsage {
+            let response: ExtraUsageResponse = serde_json::from_str(&format!(
+                r#"{{"is_enabled": true, "monthly_limit": 5000, "used_credits": 1234, "utilization": 24.68, "currency": "{currency}"}}"#
+            ))
+            .unwrap();
+            ExtraUsage::from(&response)
+        };
+        let jpy = extra("JPY");
+        assert_eq!(jpy.monthly_limit, Some(5000.0));
+        assert_eq!(jpy.used_credits, Some(1234.0));
+        assert_eq!(jpy.currency, "JPY");
+        assert_eq!(extra("EUR").used_credits, Some(12.34));
+        assert_eq!(extra("KWD").used_credits, Some(1.234));
+    }
+
+    #[test]
+    fn test_disabled_extra_usage_has_no_amounts() {
+        let response: UsageResponse = serde_json::from_str(
+            r#"{"extra_usage": {"is_enabled": false, "monthly_limit": null, "used_credits": null, "utilization": null, "currency": "EUR"}}"#,
+        )
+        .unwrap();
+        let extra = response.extra_usage.as_ref().map(ExtraUsage::from).unwrap();
+        assert!(!extra.enabled);
+        assert_eq!(extra.monthly_limit, None);
+        assert_eq!(extra.currency, "EUR");
+    }
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> reqwest::Response {
+        let mut builder = http::Response::builder().status(status);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body("").unwrap().into()
+    }
+
+    #[test]
+    fn test_error_responses_are_classified() {
+        let expired = ClaudeError::from_response(
+            &response(403, &[("content-type", "application/json")]),
+            Some("org"),
+        );
+        assert!(matches!(expired, Some(ClaudeError::SessionExpired(_))));
+
+        let challenge = ClaudeError::from_response(
+            &response(403, &[("content-type", "text/html; charset=UTF-8")]),
+            Some("org"),
+        );
+        assert!(matches!(challenge, Some(ClaudeError::CloudflareChallenge)));
+
+        let mitigated =
+            ClaudeError::from_response(&response(429, &[("cf-mitigated", "challenge")]), None);
+        assert!(matches!(mitigated, Some(ClaudeError::CloudflareChallenge)));
+
+        let missing = ClaudeError::from_response(&response(404, &[]), Some("org"));
+        assert!(matches!(missing, Some(ClaudeError::OrganizationNotFound(id)) if id == "org"));
+
+        assert!(ClaudeError::from_response(&response(404, &[]), None).is_none());
+        assert!(ClaudeError::from_response(&response(503, &[]), Some("org")).is_none());
+    }
+
+    #[test]
+    fn test_parse_body_detects_html_interstitial() {
+        let html = parse_body::<UsageResponse>("<!DOCTYPE html><title>Just a moment...</title>");
+        assert!(matches!(html, Err(ClaudeError::CloudflareChallenge)));
+
+        let schema = parse_body::<Vec<OrganizationResponse>>(r#"{"error": "nope"}"#);
+        assert!(matches!(schema, Err(ClaudeError::Schema(_))));
+    }
+
+    #[test]
+    fn test_session_expired_is_classified_as_auth() {
+        let error = ClaudeError::from_response(&response(401, &[]), Some("org")).unwrap();
+        let error = anyhow::Error::new(error).context("Failed to collect");
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Auth);
+        assert!(matches!(
+            error.downcast_ref::<ClaudeError>(),
+            Some(ClaudeError::SessionExpired(_))
+        ));
+    }
+
+    #[test]
+    fn test_only_the_selected_client_is_built() {
+        let collector = |config: ClaudeConfig| {
+            ClaudeCollector::new(
+                config,
+                CookieJar::new(&crate::config::CookiejarConfig::default()),
+                StateStore::default(),
+                RetryPolicy::default(),
+            )
+        };
+        // `base_url` without a host only matters for cookies.
+        let hostless = || ClaudeConfig {
+            base_url: "file:///claude".to_string(),
+            ..ClaudeConfig::default()
+        };
+        assert!(collector(hostless()).is_err());
+        let oauth = collector(ClaudeConfig {
+            auth: ClaudeAuth::Oauth,
+            credentials_path: Some("/nonexistent/.credentials.json".into()),
+            ..hostless()
+        })
+        .unwrap();
+        assert!(matches!(oauth.api, Some(ClaudeApi::Oauth(_))));
+
+        // A disabled collector is built without a client.
+        let disabled = collector(ClaudeConfig {
+            enabled: false,
+            ..hostless()
+        })
+        .unwrap();
+        assert!(disabled.api.is_none());
+    }
+
+    #[test]
+    fn test_session_validity_is_reported_per_organization() {
+        let collector = ClaudeCollector::new(
+            ClaudeConfig::default(),
+            CookieJar::new(&crate::config::CookiejarConfig::default()),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let org = |id: &str| Organization {
+            id: id.to_string(),
+            name: id.to_string(),
+        };
+        let organizations = [org("personal"), org("team")];
+        let snapshot = || ClaudeUsageSnapshot {
+            organization_id: "personal".to_string(),
+            fetched_at: Utc::now(),
+            metrics: Vec::new(),
+            extra_usage: None,
+        };
+        let expired = || {
+            let error = ClaudeError::from_response(&response(401, &[]), Some("team")).unwrap();
+            Err(anyhow::Error::new(error))
+        };
+        let validity = |samples: &[Sample]| -> Vec<(String, Value)> {
+            samples
+                .iter()
+                .filter(|s| s.descriptor.name == "claude.session.valid")
+                .map(|s| {
+                    let org = s
+                        .attributes
+                        .iter()
+                        .find(|(k, _)| k == "organization_id")
+                        .map(|(_, v)| v.clone())
+                        .unwrap_or_default();
+                    (org, s.value)
+                })
+                .collect()
+        };
+
+        let samples = collector
+            .organization_samples(&organizations, vec![Ok(snapshot()), expired()])
+            .unwrap();
+        assert_eq!(
+            validity(&samples),
+            [
+                ("personal".to_string(), Value::I64(1)),
+                ("team".to_string(), Value::I64(0)),
+            ]
+        );
+
+        // When every organization fails, the failure samples name them.
+        let error = collector
+            .organization_samples(
+                &organizations,
+                vec![Err(anyhow::anyhow!("timed out")), expired()],
+            )
+            .unwrap_err();
+        assert_eq!(
+            validity(&collector.failure_samples(&error)),
+            [("team".to_string(), Value::I64(0))]
+        );
+        // Without a configured organization there is nothing to label.
+        assert!(
+            collector
+                .failure_samples(&expired().unwrap_err())
+                .is_empty()
+        );
+
+        // A session rejected before the listing marks the configured
+        // organizations, whose series the next successful run overwrites.
+        let collector = ClaudeCollector::new(
+            ClaudeConfig {
+                organization_id: Some("personal".to_string()),
+                ..ClaudeConfig::default()
+            },
+            CookieJar::new(&crate::config::CookiejarConfig::default()),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let failed = collector.failure_samples(&expired().unwrap_err());
+        let samples = collector
+            .organization_samples(&organizations[..1], vec![Ok(snapshot())])
+            .unwrap();
+        assert_eq!(validity(&failed), [("personal".to_string(), Value::I64(0))]);
+        assert_eq!(
+            validity(&samples),
+            [("personal".to_string(), Value::I64(1))]
+        );
+        let session = |samples: &[Sample]| {
+            samples
+                .iter()
+                .find(|s| s.descriptor.name == "claude.session.valid")
+                .map(|s| s.attributes.clone())
+        };
+        assert_eq!(session(&failed), session(&samples));
+    }
+
+    #[test]
+    fn test_endpoints_default_to_claude_ai() {
+        let endpoints = ClaudeEndpoints::default();
+        assert_eq!(endpoints.cookie_host, ".claude.ai");
+        assert_eq!(
+            endpoints.organizations_url(),
+            "https://claude.ai/api/organizations"
+        );
+        assert_eq!(
+            endpoints.usage_url("org"),
+            "https://claude.ai/api/organizations/org/usage"
+        );
+
+        let config = ClaudeConfig {
+            base_url: "http://127.0.0.1:8080/".to_string(),
+            cookie_host: Some("claude.example.com".to_string()),
+            usage_path: "/v2/usage?org={organization_id}".to_string(),
+            ..ClaudeConfig::default()
+        };
+        let endpoints = ClaudeEndpoints::from_config(&config).unwrap();
+        assert_eq!(endpoints.cookie_host, "claude.example.com");
+        assert_eq!(
+            endpoints.usage_url("org"),
+            "http://127.0.0.1:8080/v2/usage?org=org"
+        );
+    }
+
+    fn window(utilization: f64, resets_at: Option<DateTime<Utc>>) -> UsageMetric {
+        UsageMetric {
+            name: "five_hour".to_string(),
+            utilization,
+            seconds_to_reset: None,
+            resets_at,
+            recognized: true,
+        }
+    }
+
+    #[test]
+    fn test_window_reset_reports_peak_of_closed_window() {
+        let now = Utc::now();
+        let first = now + Duration::hours(2);
+        let mut tracker = WindowTracker::default();
+
+        assert!(
+            tracker
+                .observe("org", &window(40.0, Some(first)), now)
+                .is_none()
+        );
+        assert!(
+            tracker
+                .observe("org", &window(90.0, Some(first)), now)
+                .is_none()
+        );
+        // Jitter in resets_at is not a new window.
+        let jittered = first + Duration::seconds(1);
+        assert!(
+            tracker
+                .observe("org", &window(70.0, Some(jittered)), now)
+                .is_none()
+        );
+        assert_eq!(tracker.previous_peak("org", &window(0.0, None)), None);
+
+        let second = first + Duration::hours(5);
+        let closed = tracker.observe("org", &window(5.0, Some(second)), now);
+        assert_eq!(
+            closed,
+            Some(ClosedWindow {
+                resets_at: first,
+                peak: 90.0
+            })
+        );
+        assert_eq!(tracker.previous_peak("org", &window(0.0, None)), Some(90.0));
+        assert!(tracker.previous_peak("other", &window(0.0, None)).is_none());
+    }
+
+    #[test]
+    fn test_idle_window_closes_once_its_reset_time_passed() {
+        let now = Utc::now();
+        let mut tracker = WindowTracker::default();
+        let resets_at = now + Duration::hours(1);
+        tracker.observe("org", &window(30.0, Some(resets_at)), now);
+
+        assert!(tracker.observe("org", &window(0.0, None), now).is_none());
+        let later = resets_at + Duration::minutes(1);
+        let closed = tracker.observe("org", &window(0.0, None), later).unwrap();
+        assert_eq!(closed.peak, 30.0);
+
+        // The next window starting is not another reset.
+        let next = later + Duration::hours(5);
+        assert!(
+            tracker
+                .observe("org", &window(10.0, Some(next)), later)
+                .is_none()
+        );
+        assert!(tracker.observe("org", &window(0.0, None), later).is_none());
+    }
+}
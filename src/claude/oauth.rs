@@ -0,0 +1,468 @@
+//! Claude usage through the OAuth credentials Claude Code keeps on disk, for
+//! hosts without a browser session.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use tracing::info;
+
+use super::{
+    ClaudeError, ClaudeUsageSnapshot, ExtraUsage, Organization, UsageResponse, parse_body,
+};
+use crate::config::ClaudeConfig;
+use crate::retry::{self, RetryPolicy};
+
+/// OAuth client ID of Claude Code, which issued the stored refresh token.
+const CLIENT_ID: &str = "9d1c250a-e61b-44d9-88ed-5944d1962f5e";
+const BETA_HEADER: &str = "oauth-2025-04-20";
+/// Refresh this long before the access token expires.
+const REFRESH_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
+// ============================================================================
+// Credentials
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CredentialsFile {
+    claude_ai_oauth: Option<StoredToken>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Unix time in milliseconds.
+    expires_at: Option<i64>,
+}
+
+impl StoredToken {
+    fn expires_soon(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .and_then(DateTime::from_timestamp_millis)
+            .is_some_and(|expires_at| expires_at - REFRESH_MARGIN <= now)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileResponse {
+    organization: Option<ProfileOrganization>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileOrganization {
+    uuid: String,
+    name: String,
+}
+
+fn read_token(path: &Path) -> anyhow::Result<StoredToken> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read Claude Code credentials {}", path.display()))?;
+    let file: CredentialsFile = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse Claude Code credentials {}", path.display()))?;
+    file.claude_ai_oauth.with_context(|| {
+        format!(
+            "{} has no claudeAiOauth entry; log in to Claude Code with a Claude subscription",
+            path.display()
+        )
+    })
+}
+
+/// Writes refreshed tokens back, keeping every other field, so Claude Code on
+/// the same host keeps working after the refresh token is rotated.
+fn write_token(path: &Path, token: &StoredToken) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read Claude Code credentials {}", path.display()))?;
+    let mut file: serde_json::Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse Claude Code credentials {}", path.display()))?;
+    let oauth = file
+        .get_mut("claudeAiOauth")
+        .and_then(|value| value.as_object_mut())
+        .context("Claude Code credentials lost their claudeAiOauth entry")?;
+    oauth.insert("accessToken".to_string(), token.access_token.clone().into());
+    if let Some(refresh_token) = &token.refresh_token {
+        oauth.insert("refreshToken".to_string(), refresh_token.clone().into());
+    }
+    if let Some(expires_at) = token.expires_at {
+        oauth.insert("expiresAt".to_string(), expires_at.into());
+    }
+
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(&file)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+// ============================================================================
+// OAuth Client
+// ============================================================================
+
+/// Where the OAuth client sends its requests, from the `oauth_*` settings of
+/// [`ClaudeConfig`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthEndpoints {
+    pub base_url: String,
+    pub usage_path: String,
+    pub profile_path: String,
+    pub token_url: String,
+}
+
+impl OAuthEndpoints {
+    pub fn from_config(config: &ClaudeConfig) -> Self {
+        Self {
+            base_url: config.oauth_base_url.trim_end_matches('/').to_string(),
+            usage_path: config.oauth_usage_path.clone(),
+            profile_path: config.oauth_profile_path.clone(),
+            token_url: config.oauth_token_url.clone(),
+        }
+    }
+
+    fn usage_url(&self) -> String {
+        format!("{}{}", self.base_url, self.usage_path)
+    }
+
+    fn profile_url(&self) -> String {
+        format!("{}{}", self.base_url, self.profile_path)
+    }
+}
+
+impl Default for OAuthEndpoints {
+    /// api.anthropic.com and console.anthropic.com.
+    fn default() -> Self {
+        Self::from_config(&ClaudeConfig::default())
+    }
+}
+
+/// Fetches Claude plan usage with Claude Code's OAuth access token,
+/// refreshing it when it is about to expire.
+#[derive(Debug, Clone)]
+pub struct ClaudeOAuthClient {
+    credentials_path: PathBuf,
+    http_client: reqwest::Client,
+    endpoints: OAuthEndpoints,
+    retry: RetryPolicy,
+}
+
+impl ClaudeOAuthClient {
+    pub fn new(credentials_path: PathBuf, timeout: Duration) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            credentials_path,
+            http_client,
+            endpoints: OAuthEndpoints::default(),
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Points the client at other endpoints than Anthropic's, e.g. a stub.
+    pub fn with_endpoints(mut self, endpoints: OAuthEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Replaces the default retry policy for API requests.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fetches usage for the organization the credentials belong to. The
+    /// snapshot's `organization_id` is empty; see [`Self::fetch_organization`].
+    pub async fn fetch_usage(&self) -> anyhow::Result<ClaudeUsageSnapshot> {
+        let url = self.endpoints.usage_url();
+        let body = self.get(&url, "claude.oauth_usage").await?;
+        let usage_response = parse_body::<UsageResponse>(&body)
+            .with_context(|| format!("Failed to parse usage response: {}", body))?;
+
+        Ok(ClaudeUsageSnapshot {
+            organization_id: String::new(),
+            fetched_at: Utc::now(),
+            extra_usage: usage_response.extra_usage.as_ref().map(ExtraUsage::from),
+            metrics: usage_response.into(),
+        })
+    }
+
+    /// Looks up the organization the credentials belong to.
+    pub async fn fetch_organization(&self) -> anyhow::Result<Organization> {
+        let url = self.endpoints.profile_url();
+        let body = self.get(&url, "claude.oauth_profile").await?;
+        let profile = parse_body::<ProfileResponse>(&body)
+            .with_context(|| format!("Failed to parse profile response: {}", body))?;
+        let organization = profile
+            .organization
+            .context("Claude profile has no organization")?;
+        Ok(Organization {
+            id: organization.uuid,
+            name: organization.name,
+        })
+    }
+
+    /// GETs `url` with a bearer token, refreshing it first if it expires soon
+    /// and once more if the API rejects it anyway.
+    async fn get(&self, url: &str, operation: &'static str) -> anyhow::Result<String> {
+        let mut token = read_token(&self.credentials_path)?;
+        let mut refreshed = false;
+        if token.expires_soon(Utc::now()) {
+            token = self.refresh(&token).await?;
+            refreshed = true;
+        }
+
+        loop {
+            match self.try_get(url, operation, &token.access_token).await {
+                Err(e) if !refreshed && is_rejected(&e) => {
+                    info!("Claude OAuth access token was rejected, refreshing");
+                    token = self.refresh(&token).await?;
+                    refreshed = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn try_get(
+        &self,
+        url: &str,
+        operation: &'static str,
+        access_token: &str,
+    ) -> anyhow::Result<String> {
+        self.retry
+            .run(operation, || async {
+                let response = self
+                    .http_client
+                    .get(url)
+                    .bearer_auth(access_token)
+                    .header("anthropic-beta", BETA_HEADER)
+                    .send()
+                    .await
+                    .context("Failed to send request to Claude API")?;
+                if !response.status().is_success()
+                    && let Some(error) = ClaudeError::from_response(&response, None)
+                {
+                    return Err(error.into());
+                }
+                retry::check_status(response, "Claude API")?
+                    .text()
+                    .await
+                    .context("Failed to read response body")
+            })
+            .await
+    }
+
+    async fn refresh(&self, token: &StoredToken) -> anyhow::Result<StoredToken> {
+        let refresh_token = token.refresh_token.as_deref().context(
+            "Claude Code credentials have no refresh token; log in to Claude Code again",
+        )?;
+        let body = serde_json::json!({
+            "grant_type": "refresh_token",
+            "refresh_token": refresh_token,
+            "client_id": CLIENT_ID,
+        });
+
+        let response = self
+            .retry
+            .run("claude.oauth_refresh", || async {
+                let request = self.http_client.post(&self.endpoints.token_url).json(&body);
+                let response = request
+                    .send()
+                    .await
+                    .context("Failed to send request to Claude OAuth token endpoint")?;
+                if matches!(response.status().as_u16(), 400 | 401 | 403) {
+                    // An invalid or revoked refresh token needs a new login.
+                    let error = response.error_for_status_ref().unwrap_err();
+                    return Err(ClaudeError::SessionExpired(error).into());
+                }
+                retry::check_status(response, "Claude OAuth token endpoint")?
+                    .json::<TokenResponse>()
+                    .await
+                    .context("Failed to parse Claude OAuth token response")
+            })
+            .await?;
+
+        let refreshed = StoredToken {
+            access_token: response.access_token,
+            refresh_token: response
+                .refresh_token
+                .or_else(|| token.refresh_token.clone()),
+            expires_at: response
+                .expires_in
+                .map(|seconds| (Utc::now() + TimeDelta::seconds(seconds)).timestamp_millis()),
+        };
+        write_token(&self.credentials_path, &refreshed)?;
+        info!("Refreshed Claude OAuth access token");
+        Ok(refreshed)
+    }
+}
+
+fn is_rejected(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ClaudeError>(),
+        Some(ClaudeError::SessionExpired(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_credentials(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "claude-usage-metrics-{}-{name}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_token_expiry_margin() {
+        let now = Utc::now();
+        let token = |expires_at: Option<DateTime<Utc>>| StoredToken {
+            access_token: "a".to_string(),
+            refresh_token: None,
+            expires_at: expires_at.map(|t| t.timestamp_millis()),
+        };
+        assert!(token(Some(now + TimeDelta::minutes(1))).expires_soon(now));
+        assert!(!token(Some(now + TimeDelta::hours(1))).expires_soon(now));
+        assert!(!token(None).expires_soon(now));
+    }
+
+    #[test]
+    fn test_refreshed_token_is_written_back_preserving_other_fields() {
+        let path = temp_credentials(
+            "write",
+            r#"{"claudeAiOauth":{"accessToken":"old","refreshToken":"r1","expiresAt":1,"scopes":["user:inference"],"subscriptionType":"max"},"other":true}"#,
+        );
+        assert_eq!(read_token(&path).unwrap().access_token, "old");
+
+        write_token(
+            &path,
+            &StoredToken {
+                access_token: "new".to_string(),
+                refresh_token: Some("r2".to_string()),
+                expires_at: Some(2),
+            },
+        )
+        .unwrap();
+
+        let file: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file["claudeAiOauth"]["accessToken"], "new");
+        assert_eq!(file["claudeAiOauth"]["refreshToken"], "r2");
+        assert_eq!(file["claudeAiOauth"]["expiresAt"], 2);
+        assert_eq!(file["claudeAiOauth"]["subscriptionType"], "max");
+        assert_eq!(file["other"], true);
+    }
+
+    #[test]
+    fn test_missing_oauth_entry_is_reported() {
+        let path = temp_credentials("missing", r#"{"somethingElse":{}}"#);
+        let err = read_token(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("no claudeAiOauth entry"));
+    }
+
+    /// Serves one canned JSON body per connection, in order, and returns the
+    /// base URL together with the request lines received.
+    fn serve(bodies: &'static [&'static str]) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (requests, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 4096];
+                let read = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                requests
+                    .send(request.lines().next().unwrap_or_default().to_string())
+                    .unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://{addr}/"), received)
+    }
+
+    #[tokio::test]
+    async fn test_collector_needs_organization_from_configured_endpoints() {
+        use crate::claude::ClaudeCollector;
+        use crate::collector::{Collector, Value};
+        use crate::config::{ClaudeAuth, CookiejarConfig};
+        use crate::cookiejar::CookieJar;
+        use crate::state::StateStore;
+
+        let (base_url, requests) = serve(&[
+            r#"{"organization": null}"#,
+            r#"{"organization": {"uuid": "org-1", "name": "Personal"}}"#,
+            r#"{"five_hour": {"utilization": 12.0, "resets_at": null}}"#,
+        ]);
+        let path = temp_credentials(
+            "stub",
+            r#"{"claudeAiOauth":{"accessToken":"a","refreshToken":"r","expiresAt":null}}"#,
+        );
+        let config = ClaudeConfig {
+            auth: ClaudeAuth::Oauth,
+            credentials_path: Some(path.clone()),
+            oauth_base_url: base_url,
+            ..ClaudeConfig::default()
+        };
+        let collector = ClaudeCollector::new(
+            config,
+            CookieJar::new(&CookiejarConfig::default()),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+
+        // Without an organization there is no ID to key the samples by.
+        let err = collector.collect().await.unwrap_err();
+        assert!(format!("{err:#}").contains("Claude profile has no organization"));
+
+        let samples = collector.collect().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let utilization = samples
+            .iter()
+            .find(|s| s.descriptor.name == "claude.usage.utilization")
+            .unwrap();
+        assert_eq!(utilization.value, Value::F64(0.12));
+        assert!(
+            utilization
+                .attributes
+                .contains(&("organization_id".into(), "org-1".into()))
+        );
+        let requests: Vec<String> = requests.try_iter().collect();
+        assert_eq!(
+            requests,
+            [
+                "GET /api/oauth/profile HTTP/1.1",
+                "GET /api/oauth/profile HTTP/1.1",
+                "GET /api/oauth/usage HTTP/1.1",
+            ]
+        );
+    }
+}
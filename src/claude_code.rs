@@ -0,0 +1,678 @@
+//! Token usage from the session transcripts Claude Code writes locally, one
+//! `*.jsonl` file per session under `~/.claude/projects/<project>/`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, instrument};
+
+use crate::collector::{Collector, Descriptor, Sample};
+use crate::config::ClaudeCodeConfig;
+use crate::state::StateStore;
+
+/// Name of the collector's file in the state directory.
+const STATE_NAME: &str = "claude_code";
+
+/// Name of the file holding the totals already reported as increments.
+const REPORTED_STATE_NAME: &str = "claude_code_reported";
+
+/// Message keys remembered per project to recognize copied messages.
+const RECENT_MESSAGES: usize = 10_000;
+
+/// Usage already reported as counter increments, by project and model.
+type ReportedUsage = BTreeMap<String, BTreeMap<String, TokenUsage>>;
+
+// ============================================================================
+// Transcript Types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptEntry {
+    message: Option<TranscriptMessage>,
+    request_id: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptMessage {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<MessageUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// Token counts summed over assistant messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub messages: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl TokenUsage {
+    fn add(&mut self, usage: &MessageUsage) {
+        self.messages += 1;
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+        self.cache_read_input_tokens += usage.cache_read_input_tokens;
+    }
+
+    /// Usage accumulated since `earlier`.
+    fn since(&self, earlier: &Self) -> Self {
+        Self {
+            messages: self.messages.saturating_sub(earlier.messages),
+            input_tokens: self.input_tokens.saturating_sub(earlier.input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(earlier.output_tokens),
+            cache_creation_input_tokens: self
+                .cache_creation_input_tokens
+                .saturating_sub(earlier.cache_creation_input_tokens),
+            cache_read_input_tokens: self
+                .cache_read_input_tokens
+                .saturating_sub(earlier.cache_read_input_tokens),
+        }
+    }
+}
+
+/// Cumulative usage of one project directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectUsage {
+    /// Usage per model ID (e.g. `claude-opus-4-1-20250805`).
+    pub models: BTreeMap<String, TokenUsage>,
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// Transcript Reader
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TranscriptState {
+    /// Read position per transcript, keyed by path relative to the projects
+    /// directory.
+    files: BTreeMap<String, FileState>,
+    projects: BTreeMap<String, ProjectUsage>,
+    /// Messages already counted, per project.
+    #[serde(default)]
+    recent: BTreeMap<String, RecentMessages>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FileState {
+    offset: u64,
+}
+
+/// The latest `RECENT_MESSAGES` message keys (`id:requestId`) of a project,
+/// so that each message is counted once. Claude Code writes one line per
+/// content block, each repeating the message's usage, and copies earlier
+/// messages into the new transcript when a session is resumed or forked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "VecDeque<String>", into = "VecDeque<String>")]
+struct RecentMessages {
+    order: VecDeque<String>,
+    keys: HashSet<String>,
+}
+
+impl RecentMessages {
+    /// Remembers `key` and returns whether it was new.
+    fn insert(&mut self, key: String) -> bool {
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > RECENT_MESSAGES
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.keys.remove(&oldest);
+        }
+        true
+    }
+}
+
+impl From<VecDeque<String>> for RecentMessages {
+    fn from(order: VecDeque<String>) -> Self {
+        let keys = order.iter().cloned().collect();
+        Self { order, keys }
+    }
+}
+
+impl From<RecentMessages> for VecDeque<String> {
+    fn from(recent: RecentMessages) -> Self {
+        recent.order
+    }
+}
+
+/// Incrementally reads Claude Code transcripts, remembering how far each file
+/// has been read so that every message is counted once.
+#[derive(Debug, Clone)]
+pub struct TranscriptReader {
+    projects_dir: PathBuf,
+    state: TranscriptState,
+}
+
+impl TranscriptReader {
+    pub fn new(projects_dir: PathBuf) -> Self {
+        Self {
+            projects_dir,
+            state: TranscriptState::default(),
+        }
+    }
+
+    /// Cumulative usage per project directory name.
+    pub fn projects(&self) -> &BTreeMap<String, ProjectUsage> {
+        &self.state.projects
+    }
+
+    /// Reads the lines appended to every transcript since the last call.
+    /// Partially written lines are left for the next call; a file that shrank
+    /// is read again from the start.
+    pub fn read_new(&mut self) -> anyhow::Result<()> {
+        let mut seen = BTreeSet::new();
+        for path in transcript_files(&self.projects_dir)? {
+            let Ok(relative) = path.strip_prefix(&self.projects_dir) else {
+                continue;
+            };
+            let Some(project) = relative
+                .components()
+                .next()
+                .filter(|_| relative.components().count() > 1)
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            let key = relative.to_string_lossy().into_owned();
+
+            let file = self.state.files.entry(key.clone()).or_default();
+            let recent = self.state.recent.entry(project.clone()).or_default();
+            let usage = self.state.projects.entry(project).or_default();
+            read_transcript(&path, file, recent, usage)
+                .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+            seen.insert(key);
+        }
+        // Forget offsets of deleted transcripts but keep their usage.
+        self.state.files.retain(|key, _| seen.contains(key));
+        Ok(())
+    }
+}
+
+/// Every `*.jsonl` file below `dir`, in a stable order.
+fn transcript_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to list {}", dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_transcript(
+    path: &Path,
+    state: &mut FileState,
+    recent: &mut RecentMessages,
+    usage: &mut ProjectUsage,
+) -> anyhow::Result<()> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        // Deleted since the directory was listed.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let len = file.metadata()?.len();
+    if len < state.offset {
+        *state = FileState::default();
+    }
+    if len == state.offset {
+        return Ok(());
+    }
+    file.seek(SeekFrom::Start(state.offset))?;
+
+    let mut reader = BufReader::new(file.take(len - state.offset));
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || !line.ends_with(b"\n") {
+            break;
+        }
+        state.offset += read as u64;
+        match serde_json::from_slice::<TranscriptEntry>(&line) {
+            Ok(entry) => add_entry(entry, recent, usage),
+            Err(e) => debug!(path = %path.display(), error = %e, "Skipping transcript line"),
+        }
+    }
+    Ok(())
+}
+
+fn add_entry(entry: TranscriptEntry, recent: &mut RecentMessages, usage: &mut ProjectUsage) {
+    let Some(TranscriptMessage {
+        id,
+        model: Some(model),
+        usage: Some(message_usage),
+    }) = entry.message
+    else {
+        return;
+    };
+    // Placeholder messages Claude Code inserts itself, e.g. after an error.
+    if model == "<synthetic>" {
+        return;
+    }
+    if let Some(id) = id {
+        let key = format!("{id}:{}", entry.request_id.unwrap_or_default());
+        if !recent.insert(key) {
+            return;
+        }
+    }
+
+    usage.models.entry(model).or_default().add(&message_usage);
+    if let Some(timestamp) = entry.timestamp {
+        usage.last_message_at = usage.last_message_at.max(Some(timestamp));
+    }
+}
+
+// ============================================================================
+// Claude Code Metrics Collection
+// ============================================================================
+
+static TOKENS: Descriptor = Descriptor {
+    name: "claude_code.tokens",
+    description: "Tokens used by Claude Code sessions, by token type",
+    unit: "{token}",
+};
+
+static MESSAGES: Descriptor = Descriptor {
+    name: "claude_code.messages",
+    description: "Assistant messages in Claude Code sessions",
+    unit: "{message}",
+};
+
+static LAST_MESSAGE: Descriptor = Descriptor {
+    name: "claude_code.last_message_timestamp",
+    description: "Unix time of the latest assistant message in a project",
+    unit: "s",
+};
+
+pub struct ClaudeCodeCollector {
+    config: ClaudeCodeConfig,
+    store: StateStore,
+    /// Loaded from the state store on the first run.
+    reader: Mutex<Option<TranscriptReader>>,
+    /// Loaded from the state store on the first run, so that a restart does
+    /// not report the lifetime totals again.
+    reported: Mutex<Option<ReportedUsage>>,
+}
+
+impl ClaudeCodeCollector {
+    pub fn new(config: ClaudeCodeConfig, store: StateStore) -> Self {
+        Self {
+            config,
+            store,
+            reader: Mutex::default(),
+            reported: Mutex::default(),
+        }
+    }
+
+    /// Reads new transcript lines on a blocking thread and persists the
+    /// updated offsets.
+    async fn read_transcripts(&self) -> anyhow::Result<BTreeMap<String, ProjectUsage>> {
+        let reader = self.reader.lock().unwrap().take();
+        let mut reader = match reader {
+            Some(reader) => reader,
+            None => {
+                let projects_dir = self
+                    .config
+                    .projects_dir()
+                    .context("Claude Code transcript directory is not configured")?;
+                TranscriptReader {
+                    projects_dir,
+                    state: self.store.load(STATE_NAME),
+                }
+            }
+        };
+
+        let store = self.store.clone();
+        let (reader, result) = tokio::task::spawn_blocking(move || {
+            let result = reader
+                .read_new()
+                .and_then(|()| store.save(STATE_NAME, &reader.state));
+            (reader, result)
+        })
+        .await
+        .context("Transcript reader task failed")?;
+
+        let projects = reader.projects().clone();
+        *self.reader.lock().unwrap() = Some(reader);
+        result?;
+        Ok(projects)
+    }
+}
+
+#[async_trait]
+impl Collector for ClaudeCodeCollector {
+    fn name(&self) -> &'static str {
+        "claude_code"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Code"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "claude-code-transcripts"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "claude_code_transcripts_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Reading Claude Code transcripts");
+
+        let projects = self.read_transcripts().await?;
+
+        let mut samples = Vec::new();
+        let mut reported = self.reported.lock().unwrap();
+        let reported = reported.get_or_insert_with(|| self.store.load(REPORTED_STATE_NAME));
+        for (project, usage) in &projects {
+            let reported = reported.entry(project.clone()).or_default();
+            for (model, totals) in &usage.models {
+                let increment =
+                    totals.since(&reported.insert(model.clone(), *totals).unwrap_or_default());
+
+                let token_types = [
+                    ("input", increment.input_tokens),
+                    ("output", increment.output_tokens),
+                    ("cache_creation", increment.cache_creation_input_tokens),
+                    ("cache_read", increment.cache_read_input_tokens),
+                ];
+                for (token_type, tokens) in token_types {
+                    samples.push(
+                        Sample::counter(&TOKENS, tokens)
+                            .with_attribute("project", project.clone())
+                            .with_attribute("model", model.clone())
+                            .with_attribute("type", token_type),
+                    );
+                }
+                samples.push(
+                    Sample::counter(&MESSAGES, increment.messages)
+                        .with_attribute("project", project.clone())
+                        .with_attribute("model", model.clone()),
+                );
+            }
+            if let Some(at) = usage.last_message_at {
+                samples.push(
+                    Sample::f64(&LAST_MESSAGE, at.timestamp() as f64)
+                        .with_attribute("project", project.clone()),
+                );
+            }
+        }
+
+        self.store.save(REPORTED_STATE_NAME, &*reported)?;
+
+        info!(
+            projects = projects.len(),
+            "Read Claude Code transcript usage"
+        );
+
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn assistant_line(id: &str, model: &str, input: u64, output: u64) -> String {
+        format!(
+            r#"{{"type":"assistant","requestId":"req_{id}","timestamp":"2026-01-01T00:00:00Z","message":{{"id":"msg_{id}","model":"{model}","usage":{{"input_tokens":{input},"output_tokens":{output},"cache_creation_input_tokens":10,"cache_read_input_tokens":20}}}}}}"#
+        ) + "\n"
+    }
+
+    fn temp_projects(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-usage-metrics-{}-{name}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("-home-me-repo")).unwrap();
+        dir
+    }
+
+    fn append(path: &Path, contents: &str) {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reads_appended_lines_once_per_message() {
+        let dir = temp_projects("tail");
+        let transcript = dir.join("-home-me-repo/session.jsonl");
+        let mut reader = TranscriptReader::new(dir.clone());
+
+        // The second line repeats the first message for another content block.
+        append(
+            &transcript,
+            &(assistant_line("1", "claude-opus", 3, 5) + &assistant_line("1", "claude-opus", 3, 5)),
+        );
+        append(&transcript, r#"{"type":"user","message":{"role":"user"}}"#);
+        append(&transcript, "\n");
+        reader.read_new().unwrap();
+
+        // A partially written line is only counted once it is complete.
+        let line = assistant_line("2", "claude-sonnet", 7, 11);
+        let (head, tail) = line.split_at(20);
+        append(&transcript, head);
+        reader.read_new().unwrap();
+        let usage = &reader.projects()["-home-me-repo"];
+        assert_eq!(usage.models.len(), 1);
+        append(&transcript, tail);
+        reader.read_new().unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let usage = &reader.projects()["-home-me-repo"];
+        assert_eq!(
+            usage.models["claude-opus"],
+            TokenUsage {
+                messages: 1,
+                input_tokens: 3,
+                output_tokens: 5,
+                cache_creation_input_tokens: 10,
+                cache_read_input_tokens: 20,
+            }
+        );
+        assert_eq!(usage.models["claude-sonnet"].output_tokens, 11);
+        assert_eq!(
+            usage.last_message_at,
+            Some("2026-01-01T00:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_truncated_transcript_is_read_again_and_deleted_usage_kept() {
+        let dir = temp_projects("truncate");
+        let transcript = dir.join("-home-me-repo/session.jsonl");
+        let mut reader = TranscriptReader::new(dir.clone());
+
+        append(
+            &transcript,
+            &(assistant_line("1", "claude-opus", 1, 1) + &assistant_line("2", "claude-opus", 1, 1)),
+        );
+        reader.read_new().unwrap();
+        std::fs::write(&transcript, assistant_line("3", "claude-opus", 1, 1)).unwrap();
+        reader.read_new().unwrap();
+        assert_eq!(
+            reader.projects()["-home-me-repo"].models["claude-opus"].messages,
+            3
+        );
+
+        std::fs::remove_file(&transcript).unwrap();
+        reader.read_new().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(reader.state.files.is_empty());
+        assert_eq!(
+            reader.projects()["-home-me-repo"].models["claude-opus"].messages,
+            3
+        );
+    }
+
+    #[test]
+    fn test_messages_copied_into_resumed_session_are_counted_once() {
+        let dir = temp_projects("resume");
+        let mut reader = TranscriptReader::new(dir.clone());
+        append(
+            &dir.join("-home-me-repo/first.jsonl"),
+            &(assistant_line("1", "claude-opus", 3, 5) + &assistant_line("2", "claude-opus", 1, 1)),
+        );
+        reader.read_new().unwrap();
+
+        // Resuming copies the earlier messages before the new one.
+        append(
+            &dir.join("-home-me-repo/resumed.jsonl"),
+            &(assistant_line("1", "claude-opus", 3, 5)
+                + &assistant_line("2", "claude-opus", 1, 1)
+                + &assistant_line("3", "claude-opus", 2, 2)),
+        );
+        reader.read_new().unwrap();
+
+        // The keys seen so far survive a restart.
+        let state = serde_json::to_string(&reader.state).unwrap();
+        let mut reader = TranscriptReader {
+            projects_dir: dir.clone(),
+            state: serde_json::from_str(&state).unwrap(),
+        };
+        append(
+            &dir.join("-home-me-repo/forked.jsonl"),
+            &assistant_line("3", "claude-opus", 2, 2),
+        );
+        reader.read_new().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let usage = &reader.projects()["-home-me-repo"].models["claude-opus"];
+        assert_eq!(usage.messages, 3);
+        assert_eq!(usage.input_tokens, 6);
+    }
+
+    #[tokio::test]
+    async fn test_collector_reports_increments() {
+        let dir = temp_projects("collector");
+        let transcript = dir.join("-home-me-repo/session.jsonl");
+        let collector = ClaudeCodeCollector::new(
+            ClaudeCodeConfig {
+                enabled: true,
+                projects_dir: Some(dir.clone()),
+                ..ClaudeCodeConfig::default()
+            },
+            StateStore::default(),
+        );
+        let input_tokens = |samples: &[Sample]| {
+            samples
+                .iter()
+                .find(|s| {
+                    s.descriptor.name == "claude_code.tokens"
+                        && s.attributes
+                            .contains(&("type".to_string(), "input".to_string()))
+                })
+                .map(|s| s.value)
+        };
+
+        append(&transcript, &assistant_line("1", "claude-opus", 3, 5));
+        let samples = collector.collect().await.unwrap();
+        assert_eq!(
+            input_tokens(&samples),
+            Some(crate::collector::Value::Counter(3))
+        );
+
+        append(&transcript, &assistant_line("2", "claude-opus", 4, 5));
+        let samples = collector.collect().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            input_tokens(&samples),
+            Some(crate::collector::Value::Counter(4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restart_does_not_report_totals_again() {
+        let dir = temp_projects("restart");
+        let transcript = dir.join("-home-me-repo/session.jsonl");
+        // State files are `*.json`, so they are not mistaken for transcripts.
+        let store = StateStore::new(&crate::config::StateConfig {
+            dir: Some(dir.join("state")),
+        });
+        let collector = || {
+            ClaudeCodeCollector::new(
+                ClaudeCodeConfig {
+                    enabled: true,
+                    projects_dir: Some(dir.clone()),
+                    ..ClaudeCodeConfig::default()
+                },
+                store.clone(),
+            )
+        };
+
+        append(&transcript, &assistant_line("1", "claude-opus", 3, 5));
+        let samples = collector().collect().await.unwrap();
+        assert!(
+            samples.contains(
+                &Sample::counter(&MESSAGES, 1)
+                    .with_attribute("project", "-home-me-repo")
+                    .with_attribute("model", "claude-opus")
+            )
+        );
+
+        let samples = collector().collect().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            samples
+                .iter()
+                .filter(|s| s.descriptor.name != "claude_code.last_message_timestamp")
+                .all(|s| s.value == crate::collector::Value::Counter(0))
+        );
+    }
+}
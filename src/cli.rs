@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+/// Collects Claude, OpenRouter and GitHub Copilot usage and exports it as
+/// OpenTelemetry metrics.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// Path to the TOML configuration file.
+    #[arg(long, global = true, env = "CLAUDE_USAGE_METRICS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Defaults to the `mode` from the configuration file when omitted.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Collect from every enabled collector once, export and exit.
+    Once,
+    /// Keep running and poll each collector on its configured interval.
+    Serve,
+    /// Run a single collector and print its samples without exporting.
+    Check {
+        /// Collector name as shown by `list-collectors`.
+        collector: String,
+    },
+    /// List the available collectors and whether they are enabled.
+    ListCollectors,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_no_subcommand_defers_to_config() {
+        let cli = Cli::try_parse_from(["claude-usage-metrics"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_check_takes_collector_name() {
+        let cli = Cli::try_parse_from([
+            "claude-usage-metrics",
+            "check",
+            "github_copilot",
+            "--config",
+            "config.toml",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Check { ref collector }) if collector == "github_copilot"
+        ));
+        assert_eq!(cli.config, Some(PathBuf::from("config.toml")));
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+// ============================================================================
+// Samples
+// ============================================================================
+
+/// Static description of a metric a collector can emit.
+#[derive(Debug, PartialEq)]
+pub struct Descriptor {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub unit: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    F64(f64),
+    I64(i64),
+    /// Increment of a monotonic counter since the previous sample.
+    Counter(u64),
+    /// Fractional increment, e.g. of a cost.
+    CounterF64(f64),
+}
+
+/// A single datapoint produced by a collector, independent of any exporter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub descriptor: &'static Descriptor,
+    pub value: Value,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Sample {
+    pub fn f64(descriptor: &'static Descriptor, value: f64) -> Self {
+        Self {
+            descriptor,
+            value: Value::F64(value),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn i64(descriptor: &'static Descriptor, value: i64) -> Self {
+        Self {
+            descriptor,
+            value: Value::I64(value),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn counter(descriptor: &'static Descriptor, increment: u64) -> Self {
+        Self {
+            descriptor,
+            value: Value::Counter(increment),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn counter_f64(descriptor: &'static Descriptor, increment: f64) -> Self {
+        Self {
+            descriptor,
+            value: Value::CounterF64(increment),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Replaces each counter sample's running total with the part not in
+/// `reported` yet, so totals that are still filling up (or revised later)
+/// are never counted twice. Gauges pass through unchanged.
+pub fn into_increments(samples: Vec<Sample>, reported: &mut BTreeMap<String, f64>) -> Vec<Sample> {
+    samples
+        .into_iter()
+        .map(|mut sample| {
+            let key = format!("{}{:?}", sample.descriptor.name, sample.attributes);
+            let total = match sample.value {
+                Value::Counter(total) => total as f64,
+                Value::CounterF64(total) => total,
+                Value::F64(_) | Value::I64(_) => return sample,
+            };
+            let previous = reported.insert(key, total).unwrap_or_default();
+            let increment = (total - previous).max(0.0);
+            sample.value = match sample.value {
+                Value::Counter(_) => Value::Counter(increment as u64),
+                _ => Value::CounterF64(increment),
+            };
+            sample
+        })
+        .collect()
+}
+
+// ============================================================================
+// Collector
+// ============================================================================
+
+/// A usage data source that can be polled for samples.
+#[async_trait]
+pub trait Collector: Send + Sync {
+    /// Stable identifier used in logs and attributes (e.g. `claude`).
+    fn name(&self) -> &'static str;
+
+    /// Human readable name used in error messages (e.g. `Claude`).
+    fn display_name(&self) -> &'static str;
+
+    /// Instrumentation scope the collector's samples are recorded under.
+    fn meter_name(&self) -> &'static str;
+
+    /// Whether the collector should take part in a collection run.
+    fn enabled(&self) -> bool;
+
+    /// Extra attributes attached to every sample the collector produces.
+    fn labels(&self) -> &BTreeMap<String, String>;
+
+    /// How often the collector is polled in daemon mode.
+    fn interval(&self) -> Duration;
+
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>>;
+
+    /// Samples to record when [`collect`](Self::collect) fails, such as a
+    /// health gauge dropping to zero.
+    fn failure_samples(&self, _error: &anyhow::Error) -> Vec<Sample> {
+        Vec::new()
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Outcome of running a single collector.
+pub struct CollectorResult {
+    pub collector: Arc<dyn Collector>,
+    pub result: anyhow::Result<Vec<Sample>>,
+    /// Wall-clock time spent in [`Collector::collect`].
+    pub duration: Duration,
+}
+
+#[derive(Default)]
+pub struct Registry {
+    collectors: Vec<Arc<dyn Collector>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, collector: impl Collector + 'static) {
+        self.collectors.push(Arc::new(collector));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Collector>> {
+        self.collectors.iter()
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &Arc<dyn Collector>> {
+        self.collectors.iter().filter(|c| c.enabled())
+    }
+
+    /// Runs every enabled collector concurrently, preserving registration order.
+    pub async fn collect_all(&self) -> Vec<CollectorResult> {
+        let runs = self
+            .enabled()
+            .map(|collector| run_collector(collector.clone()));
+        futures_util::future::join_all(runs).await
+    }
+}
+
+/// Runs a single collector and attaches its configured labels to the samples.
+pub async fn run_collector(collector: Arc<dyn Collector>) -> CollectorResult {
+    let started = Instant::now();
+    let result = collector.collect().await;
+    let duration = started.elapsed();
+    let result = result.map(|samples| with_labels(collector.as_ref(), samples));
+    CollectorResult {
+        collector,
+        result,
+        duration,
+    }
+}
+
+/// Appends the collector's configured labels to every sample.
+pub fn with_labels(collector: &dyn Collector, mut samples: Vec<Sample>) -> Vec<Sample> {
+    for sample in &mut samples {
+        sample.attributes.extend(
+            collector
+                .labels()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+    }
+    samples
+}
+
+// ============================================================================
+// Error Classification
+// ============================================================================
+
+/// Coarse failure category, used to tell e.g. expired cookies apart from an
+/// unreachable API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The API rejected the credentials (HTTP 401 or 403).
+    Auth,
+    /// The API asked us to back off (HTTP 429).
+    RateLimited,
+    /// Any other non-2xx HTTP status.
+    HttpStatus,
+    /// The request did not complete within the configured timeout.
+    Timeout,
+    /// The request could not be sent or the connection failed.
+    Network,
+    /// The response body did not match the expected schema.
+    Parse,
+    /// The cookiejar-reader service could not provide cookies.
+    Cookiejar,
+    /// A bot-protection challenge was served instead of the API response.
+    Challenge,
+    /// The requested resource (e.g. an organization) does not exist.
+    NotFound,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::RateLimited => "rate_limited",
+            Self::HttpStatus => "http_status",
+            Self::Timeout => "timeout",
+            Self::Network => "network",
+            Self::Parse => "parse",
+            Self::Cookiejar => "cookiejar",
+            Self::Challenge => "challenge",
+            Self::NotFound => "not_found",
+            Self::Other => "other",
+        }
+    }
+
+    /// Classifies an error by the first recognised cause in its chain.
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            #[cfg(feature = "claude")]
+            if let Some(e) = cause.downcast_ref::<crate::claude::ClaudeError>() {
+                return e.class();
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return Self::of_reqwest(e);
+            }
+            if cause.is::<serde_json::Error>() {
+                return Self::Parse;
+            }
+            #[cfg(feature = "cookiejar")]
+            if cause.is::<tonic::Status>() || cause.is::<tonic::transport::Error>() {
+                return Self::Cookiejar;
+            }
+        }
+        Self::Other
+    }
+
+    fn of_reqwest(error: &reqwest::Error) -> Self {
+        if let Some(status) = error.status() {
+            return match status.as_u16() {
+                401 | 403 => Self::Auth,
+                429 => Self::RateLimited,
+                _ => Self::HttpStatus,
+            };
+        }
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_decode() {
+            Self::Parse
+        } else {
+            Self::Network
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_METRIC: Descriptor = Descriptor {
+        name: "test.metric",
+        description: "Test metric",
+        unit: "1",
+    };
+
+    struct StaticCollector {
+        name: &'static str,
+        enabled: bool,
+        fail: bool,
+        labels: BTreeMap<String, String>,
+    }
+
+    #[async_trait]
+    impl Collector for StaticCollector {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn display_name(&self) -> &'static str {
+            self.name
+        }
+
+        fn meter_name(&self) -> &'static str {
+            "test"
+        }
+
+        fn enabled(&self) -> bool {
+            self.enabled
+        }
+
+        fn labels(&self) -> &BTreeMap<String, String> {
+            &self.labels
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(60)
+        }
+
+        async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+            if self.fail {
+                anyhow::bail!("boom");
+            }
+            Ok(vec![
+                Sample::f64(&TEST_METRIC, 1.0).with_attribute("collector", self.name),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_skips_disabled_and_preserves_order() {
+        let mut registry = Registry::new();
+        registry.register(StaticCollector {
+            name: "a",
+            enabled: true,
+            fail: false,
+            labels: BTreeMap::new(),
+        });
+        registry.register(StaticCollector {
+            name: "b",
+            enabled: false,
+            fail: false,
+            labels: BTreeMap::new(),
+        });
+        registry.register(StaticCollector {
+            name: "c",
+            enabled: true,
+            fail: true,
+            labels: BTreeMap::new(),
+        });
+
+        let results = registry.collect_all().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].collector.name(), "a");
+        let samples = results[0].result.as_ref().unwrap();
+        assert_eq!(samples[0].value, Value::F64(1.0));
+        assert_eq!(
+            samples[0].attributes,
+            vec![("collector".to_string(), "a".to_string())]
+        );
+        assert_eq!(results[1].collector.name(), "c");
+        assert!(results[1].result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_appends_configured_labels() {
+        let mut registry = Registry::new();
+        registry.register(StaticCollector {
+            name: "a",
+            enabled: true,
+            fail: false,
+            labels: BTreeMap::from([("team".to_string(), "platform".to_string())]),
+        });
+
+        let results = registry.collect_all().await;
+        let samples = results[0].result.as_ref().unwrap();
+        assert_eq!(
+            samples[0].attributes,
+            vec![
+                ("collector".to_string(), "a".to_string()),
+                ("team".to_string(), "platform".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_class_looks_through_context() {
+        let parse = serde_json::from_str::<u32>("nope").unwrap_err();
+        let error = anyhow::Error::new(parse).context("Failed to parse usage response");
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Parse);
+
+        let error = anyhow::anyhow!("OPENROUTER_API_KEY environment variable not set");
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Other);
+    }
+
+    #[tokio::test]
+    async fn test_error_class_http_status() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for status in [
+                "401 Unauthorized",
+                "429 Too Many Requests",
+                "500 Internal Server Error",
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]);
+                let response =
+                    format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let client = reqwest::Client::new();
+        for expected in [
+            ErrorClass::Auth,
+            ErrorClass::RateLimited,
+            ErrorClass::HttpStatus,
+        ] {
+            let error = client
+                .get(format!("http://{addr}/"))
+                .send()
+                .await
+                .unwrap()
+                .error_for_status()
+                .map_err(anyhow::Error::new)
+                .unwrap_err()
+                .context("API returned non-2xx status");
+            assert_eq!(ErrorClass::of(&error), expected);
+        }
+    }
+
+    #[cfg(feature = "cookiejar")]
+    #[test]
+    fn test_error_class_cookiejar_status() {
+        let error =
+            anyhow::Error::new(tonic::Status::unavailable("down")).context("Failed to get cookies");
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Cookiejar);
+    }
+}
@@ -0,0 +1,1360 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+// ============================================================================
+// Configuration Types
+// ============================================================================
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub mode: Mode,
+    pub telemetry: TelemetryConfig,
+    pub cookiejar: CookiejarConfig,
+    pub retry: RetryConfig,
+    pub state: StateConfig,
+    pub collectors: CollectorsConfig,
+}
+
+/// Whether to collect once and exit or keep polling on each collector's interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Once,
+    Serve,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(Self::Once),
+            "serve" => Ok(Self::Serve),
+            _ => anyhow::bail!("mode must be `once` or `serve`, got {s:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub service_name: String,
+    /// Whether traces and metrics are pushed to `otlp_endpoint`. Defaults to
+    /// on when the `otlp` feature is compiled in.
+    pub otlp_enabled: bool,
+    pub otlp_endpoint: String,
+    #[serde(with = "humantime_serde")]
+    pub export_timeout: Duration,
+    pub prometheus: PrometheusConfig,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            service_name: "claude-usage-metrics".to_string(),
+            otlp_enabled: cfg!(feature = "otlp"),
+            otlp_endpoint: "http://127.0.0.1:4317".to_string(),
+            export_timeout: Duration::from_secs(10),
+            prometheus: PrometheusConfig::default(),
+        }
+    }
+}
+
+/// Built-in `/metrics` scrape endpoint, served in daemon mode.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrometheusConfig {
+    pub enabled: bool,
+    pub listen_address: SocketAddr,
+}
+
+impl Default for PrometheusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_address: SocketAddr::from(([0, 0, 0, 0], 9464)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookiejarConfig {
+    pub url: Option<String>,
+    #[serde(with = "humantime_serde")]
+    pub connect_timeout: Duration,
+}
+
+impl Default for CookiejarConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Retry policy shared by every upstream HTTP and gRPC call.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Total attempts per call, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    /// Upper bound for a single backoff; a longer `Retry-After` gives up instead.
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Where collectors keep state (such as transcript offsets) across restarts.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    /// Directory for the state files. Without one, state is kept in memory
+    /// and rebuilt after a restart.
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorsConfig {
+    pub claude: ClaudeConfig,
+    pub claude_code: ClaudeCodeConfig,
+    pub claude_api: ClaudeApiConfig,
+    pub claude_code_analytics: ClaudeCodeAnalyticsConfig,
+    pub openrouter: OpenRouterConfig,
+    pub github_copilot: GithubCopilotConfig,
+}
+
+impl CollectorsConfig {
+    /// Enables the named collector and disables every other one.
+    pub fn enable_only(&mut self, name: &str) -> anyhow::Result<()> {
+        let toggles = self.toggles();
+        let Some(&(_, feature, compiled, _)) = toggles.iter().find(|(n, ..)| *n == name) else {
+            let available: Vec<&str> = toggles
+                .iter()
+                .filter(|(_, _, compiled, _)| *compiled)
+                .map(|(n, ..)| *n)
+                .collect();
+            anyhow::bail!(
+                "Unknown collector {name:?} (available: {})",
+                available.join(", ")
+            );
+        };
+        if !compiled {
+            anyhow::bail!(
+                "Collector {name:?} is not compiled into this binary (enable the `{feature}` cargo feature)"
+            );
+        }
+        for (n, _, _, enabled) in toggles {
+            *enabled = n == name;
+        }
+        Ok(())
+    }
+
+    /// Disables collectors whose cargo feature was not compiled in.
+    fn disable_unavailable(&mut self) {
+        for (_, _, compiled, enabled) in self.toggles() {
+            *enabled &= compiled;
+        }
+    }
+
+    /// Collector name, cargo feature, whether it is compiled in and its
+    /// `enabled` flag.
+    fn toggles(&mut self) -> [(&'static str, &'static str, bool, &mut bool); 6] {
+        [
+            (
+                "claude",
+                "claude",
+                cfg!(feature = "claude"),
+                &mut self.claude.enabled,
+            ),
+            (
+                "claude_code",
+                "claude-code",
+                cfg!(feature = "claude-code"),
+                &mut self.claude_code.enabled,
+            ),
+            (
+                "claude_api",
+                "admin",
+                cfg!(feature = "admin"),
+                &mut self.claude_api.enabled,
+            ),
+            (
+                "claude_code_analytics",
+                "admin",
+                cfg!(feature = "admin"),
+                &mut self.claude_code_analytics.enabled,
+            ),
+            (
+                "openrouter",
+                "openrouter",
+                cfg!(feature = "openrouter"),
+                &mut self.openrouter.enabled,
+            ),
+            (
+                "github_copilot",
+                "github-copilot",
+                cfg!(feature = "github-copilot"),
+                &mut self.github_copilot.enabled,
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeConfig {
+    pub enabled: bool,
+    pub auth: ClaudeAuth,
+    /// Claude Code credentials file used by `auth = "oauth"`. Defaults to
+    /// `.credentials.json` in `$CLAUDE_CONFIG_DIR` or `~/.claude`.
+    pub credentials_path: Option<PathBuf>,
+    /// Shorthand for a single entry in `organization_ids`.
+    pub organization_id: Option<String>,
+    pub organization_ids: Vec<String>,
+    /// Collect every organization listed for the session instead of (or in
+    /// addition to) the configured IDs.
+    pub discover_organizations: bool,
+    /// Deployment serving the claude.ai API for `auth = "cookies"`.
+    pub base_url: String,
+    /// Domain whose browser cookies are sent. Defaults to the `base_url`
+    /// host with a leading dot, e.g. `.claude.ai`.
+    pub cookie_host: Option<String>,
+    pub organizations_path: String,
+    /// Usage endpoint path; `{organization_id}` is replaced with each ID.
+    pub usage_path: String,
+    /// Anthropic API serving the usage and profile endpoints for
+    /// `auth = "oauth"`.
+    pub oauth_base_url: String,
+    pub oauth_usage_path: String,
+    pub oauth_profile_path: String,
+    /// Endpoint refreshing the Claude Code access token.
+    pub oauth_token_url: String,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for ClaudeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auth: ClaudeAuth::default(),
+            credentials_path: None,
+            organization_id: None,
+            organization_ids: Vec::new(),
+            discover_organizations: false,
+            base_url: "https://claude.ai".to_string(),
+            cookie_host: None,
+            organizations_path: "/api/organizations".to_string(),
+            usage_path: "/api/organizations/{organization_id}/usage".to_string(),
+            oauth_base_url: "https://api.anthropic.com".to_string(),
+            oauth_usage_path: "/api/oauth/usage".to_string(),
+            oauth_profile_path: "/api/oauth/profile".to_string(),
+            oauth_token_url: "https://console.anthropic.com/v1/oauth/token".to_string(),
+            timeout: DEFAULT_HTTP_TIMEOUT,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+/// How the Claude collector authenticates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaudeAuth {
+    /// claude.ai browser session cookies borrowed from cookiejar-reader.
+    #[default]
+    Cookies,
+    /// Claude Code OAuth credentials, for hosts without a browser.
+    Oauth,
+}
+
+impl std::str::FromStr for ClaudeAuth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cookies" => Ok(Self::Cookies),
+            "oauth" => Ok(Self::Oauth),
+            _ => anyhow::bail!("Claude auth must be `cookies` or `oauth`, got {s:?}"),
+        }
+    }
+}
+
+impl ClaudeConfig {
+    /// Resolved Claude Code credentials file, or `None` when neither
+    /// `credentials_path` nor a home directory is available.
+    pub fn credentials_path(&self) -> Option<PathBuf> {
+        self.credentials_path_with(|key| std::env::var(key).ok())
+    }
+
+    fn credentials_path_with(&self, env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+        if let Some(path) = &self.credentials_path {
+            return Some(path.clone());
+        }
+        Some(claude_config_dir(env)?.join(".credentials.json"))
+    }
+
+    /// Resolved cookie domain, or `None` when `base_url` has no host.
+    pub fn cookie_host(&self) -> Option<String> {
+        if let Some(host) = &self.cookie_host {
+            return Some(host.clone());
+        }
+        let url = reqwest::Url::parse(&self.base_url).ok()?;
+        Some(format!(".{}", url.host_str()?))
+    }
+
+    /// Configured organization IDs, without duplicates, in configuration order.
+    pub fn organization_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for id in self.organization_id.iter().chain(&self.organization_ids) {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+}
+
+/// Claude Code's configuration directory: `$CLAUDE_CONFIG_DIR`, or `~/.claude`.
+fn claude_config_dir(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    match env("CLAUDE_CONFIG_DIR") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Some(PathBuf::from(env("HOME")?).join(".claude")),
+    }
+}
+
+/// Token usage read from the session transcripts Claude Code writes locally.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeCodeConfig {
+    pub enabled: bool,
+    /// Directory holding one subdirectory of `*.jsonl` transcripts per
+    /// project. Defaults to `projects` in `$CLAUDE_CONFIG_DIR` or `~/.claude`.
+    pub projects_dir: Option<PathBuf>,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for ClaudeCodeConfig {
+    fn default() -> Self {
+        Self {
+            // Off by default: it only makes sense on a machine running Claude Code.
+            enabled: false,
+            projects_dir: None,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+impl ClaudeCodeConfig {
+    /// Resolved transcript directory, or `None` when neither `projects_dir`
+    /// nor a home directory is available.
+    pub fn projects_dir(&self) -> Option<PathBuf> {
+        self.projects_dir_with(|key| std::env::var(key).ok())
+    }
+
+    fn projects_dir_with(&self, env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+        if let Some(dir) = &self.projects_dir {
+            return Some(dir.clone());
+        }
+        Some(claude_config_dir(env)?.join("projects"))
+    }
+}
+
+/// Usage and cost reports from the Anthropic Admin API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeApiConfig {
+    pub enabled: bool,
+    /// Name of the environment variable holding the Admin API key.
+    pub admin_key_env: String,
+    /// Anthropic API origin, overridable to point at a local stub.
+    pub base_url: String,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for ClaudeApiConfig {
+    fn default() -> Self {
+        Self {
+            // Off by default: it needs an organization admin key.
+            enabled: false,
+            admin_key_env: "ANTHROPIC_ADMIN_KEY".to_string(),
+            base_url: "https://api.anthropic.com".to_string(),
+            timeout: DEFAULT_HTTP_TIMEOUT,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+/// Per-actor Claude Code usage report from the Anthropic Admin API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeCodeAnalyticsConfig {
+    pub enabled: bool,
+    /// Name of the environment variable holding the Admin API key.
+    pub admin_key_env: String,
+    /// Anthropic API origin, overridable to point at a local stub.
+    pub base_url: String,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for ClaudeCodeAnalyticsConfig {
+    fn default() -> Self {
+        let api = ClaudeApiConfig::default();
+        Self {
+            enabled: false,
+            admin_key_env: api.admin_key_env,
+            base_url: api.base_url,
+            timeout: DEFAULT_HTTP_TIMEOUT,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenRouterConfig {
+    pub enabled: bool,
+    /// Name of the environment variable holding the API key, used when no
+    /// `keys` are configured.
+    pub api_key_env: String,
+    /// Named API keys, e.g. one per team or project.
+    pub keys: Vec<OpenRouterKeyConfig>,
+    /// Provisioning (management) keys for account-level data, one per account.
+    pub provisioning_keys: Vec<OpenRouterProvisioningKeyConfig>,
+    /// Report every API key of the accounts in `provisioning_keys`, so they
+    /// need not be listed in `keys`.
+    pub list_keys: bool,
+    /// Share of its limit a listed key may spend before it is flagged as
+    /// near its limit.
+    pub key_limit_warning: f64,
+    /// Export requests, tokens and spend by model and provider from the
+    /// activity of the last 30 completed days.
+    pub activity: bool,
+    /// Export the activity history of the last 30 days on the first run,
+    /// instead of only the days completed from then on.
+    pub activity_backfill: bool,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for OpenRouterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_key_env: "OPENROUTER_API_KEY".to_string(),
+            keys: Vec::new(),
+            provisioning_keys: Vec::new(),
+            list_keys: false,
+            key_limit_warning: 0.9,
+            activity: false,
+            activity_backfill: false,
+            timeout: DEFAULT_HTTP_TIMEOUT,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+impl OpenRouterConfig {
+    /// Keys to collect: the configured `keys`, or a single `default` key
+    /// read from `api_key_env` unless the keys are listed with provisioning
+    /// keys instead.
+    pub fn keys(&self) -> Vec<OpenRouterKeyConfig> {
+        if !self.keys.is_empty() || self.list_keys {
+            return self.keys.clone();
+        }
+        vec![OpenRouterKeyConfig {
+            name: "default".to_string(),
+            api_key_env: self.api_key_env.clone(),
+            account: None,
+        }]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenRouterKeyConfig {
+    pub name: String,
+    /// Name of the environment variable holding the API key.
+    pub api_key_env: String,
+    /// OpenRouter account the key belongs to. Keys of one account share its
+    /// credit balance. Defaults to `name`.
+    pub account: Option<String>,
+}
+
+impl OpenRouterKeyConfig {
+    pub fn account(&self) -> &str {
+        self.account.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenRouterProvisioningKeyConfig {
+    /// Account the key manages, matching the `account` of its API keys.
+    #[serde(default = "default_openrouter_account")]
+    pub account: String,
+    /// Name of the environment variable holding the provisioning key.
+    pub api_key_env: String,
+}
+
+fn default_openrouter_account() -> String {
+    "default".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubCopilotConfig {
+    pub enabled: bool,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for GithubCopilotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout: DEFAULT_HTTP_TIMEOUT,
+            interval: DEFAULT_INTERVAL,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// ============================================================================
+// Loading
+// ============================================================================
+
+impl Config {
+    /// Loads the configuration file at `path` (falling back to defaults when
+    /// none is given) and applies environment overrides. Call
+    /// [`Config::validate`] before using the result to collect.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env_overrides(|key| std::env::var(key).ok())?;
+        config.collectors.disable_unavailable();
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// Applies the legacy environment variables on top of the file values.
+    fn apply_env_overrides(&mut self, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        if let Some(mode) = env("CLAUDE_USAGE_METRICS_MODE") {
+            self.mode = mode.parse()?;
+        }
+        if let Some(name) = env("OTEL_SERVICE_NAME") {
+            self.telemetry.service_name = name;
+        }
+        if let Some(endpoint) = env("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            self.telemetry.otlp_endpoint = endpoint;
+        }
+        if let Some(dir) = env("CLAUDE_USAGE_METRICS_STATE_DIR") {
+            self.state.dir = Some(PathBuf::from(dir));
+        }
+        if let Some(url) = env("COOKIEJAR_URL") {
+            self.cookiejar.url = Some(url);
+        }
+        if let Some(url) = env("CLAUDE_BASE_URL") {
+            self.collectors.claude.base_url = url;
+        }
+        if let Some(auth) = env("CLAUDE_AUTH") {
+            self.collectors.claude.auth = auth.parse().context("Invalid CLAUDE_AUTH")?;
+        }
+        if let Some(org_ids) = env("CLAUDE_ORGANIZATION_ID") {
+            // Comma-separated, replacing every ID from the file.
+            let claude = &mut self.collectors.claude;
+            claude.organization_id = None;
+            claude.organization_ids = org_ids
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        let toggles = [
+            ("CLAUDE_ENABLED", &mut self.collectors.claude.enabled),
+            (
+                "CLAUDE_CODE_ENABLED",
+                &mut self.collectors.claude_code.enabled,
+            ),
+            (
+                "CLAUDE_API_ENABLED",
+                &mut self.collectors.claude_api.enabled,
+            ),
+            (
+                "CLAUDE_CODE_ANALYTICS_ENABLED",
+                &mut self.collectors.claude_code_analytics.enabled,
+            ),
+            (
+                "OPENROUTER_ENABLED",
+                &mut self.collectors.openrouter.enabled,
+            ),
+            (
+                "GITHUB_COPILOT_ENABLED",
+                &mut self.collectors.github_copilot.enabled,
+            ),
+        ];
+        for (key, enabled) in toggles {
+            if let Some(value) = env(key) {
+                *enabled = value
+                    .parse()
+                    .with_context(|| format!("{key} must be `true` or `false`, got {value:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that every enabled collector has what it needs to run.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.validate_with(|key| std::env::var(key).ok())
+    }
+
+    fn validate_with(&self, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+        let collectors = &self.collectors;
+
+        let endpoints = [
+            (
+                "telemetry.otlp_endpoint",
+                self.telemetry
+                    .otlp_enabled
+                    .then_some(&self.telemetry.otlp_endpoint),
+            ),
+            ("cookiejar.url", self.cookiejar.url.as_ref()),
+            (
+                "collectors.claude.base_url",
+                Some(&collectors.claude.base_url),
+            ),
+            (
+                "collectors.claude.oauth_base_url",
+                Some(&collectors.claude.oauth_base_url),
+            ),
+            (
+                "collectors.claude.oauth_token_url",
+                Some(&collectors.claude.oauth_token_url),
+            ),
+            (
+                "collectors.claude_api.base_url",
+                Some(&collectors.claude_api.base_url),
+            ),
+            (
+                "collectors.claude_code_analytics.base_url",
+                Some(&collectors.claude_code_analytics.base_url),
+            ),
+        ];
+        for (key, url) in endpoints {
+            if let Some(url) = url
+                && !(url.starts_with("http://") || url.starts_with("https://"))
+            {
+                problems.push(format!("{key} must be an http(s) URL, got {url:?}"));
+            }
+        }
+
+        let intervals = [
+            ("collectors.claude.interval", collectors.claude.interval),
+            (
+                "collectors.claude_code.interval",
+                collectors.claude_code.interval,
+            ),
+            (
+                "collectors.claude_api.interval",
+                collectors.claude_api.interval,
+            ),
+            (
+                "collectors.claude_code_analytics.interval",
+                collectors.claude_code_analytics.interval,
+            ),
+            (
+                "collectors.openrouter.interval",
+                collectors.openrouter.interval,
+            ),
+            (
+                "collectors.github_copilot.interval",
+                collectors.github_copilot.interval,
+            ),
+        ];
+        for (key, interval) in intervals {
+            if interval.is_zero() {
+                problems.push(format!("{key} must be greater than zero"));
+            }
+        }
+
+        if self.retry.max_attempts == 0 {
+            problems.push("retry.max_attempts must be at least 1".to_string());
+        }
+        if self.retry.initial_backoff > self.retry.max_backoff {
+            problems.push("retry.initial_backoff must not exceed retry.max_backoff".to_string());
+        }
+
+        if self.telemetry.prometheus.enabled && !cfg!(feature = "prometheus") {
+            problems.push(
+                "telemetry.prometheus.enabled requires the `prometheus` cargo feature".to_string(),
+            );
+        }
+        if self.telemetry.otlp_enabled && !cfg!(feature = "otlp") {
+            problems.push("telemetry.otlp_enabled requires the `otlp` cargo feature".to_string());
+        }
+
+        let claude_cookies =
+            collectors.claude.enabled && collectors.claude.auth == ClaudeAuth::Cookies;
+        let needs_cookiejar = claude_cookies || collectors.github_copilot.enabled;
+        if needs_cookiejar && self.cookiejar.url.is_none() {
+            problems.push("cookiejar.url (or COOKIEJAR_URL) is required by the Claude and GitHub Copilot collectors".to_string());
+        }
+        if collectors.claude.enabled {
+            let claude = &collectors.claude;
+            let paths = match claude.auth {
+                ClaudeAuth::Cookies => [
+                    (
+                        "collectors.claude.organizations_path",
+                        &claude.organizations_path,
+                    ),
+                    ("collectors.claude.usage_path", &claude.usage_path),
+                ],
+                ClaudeAuth::Oauth => [
+                    (
+                        "collectors.claude.oauth_profile_path",
+                        &claude.oauth_profile_path,
+                    ),
+                    (
+                        "collectors.claude.oauth_usage_path",
+                        &claude.oauth_usage_path,
+                    ),
+                ],
+            };
+            for (key, path) in paths {
+                if !path.starts_with('/') {
+                    problems.push(format!("{key} must start with `/`, got {path:?}"));
+                }
+            }
+        }
+        if claude_cookies {
+            let claude = &collectors.claude;
+            if !claude.usage_path.contains("{organization_id}") {
+                problems.push(
+                    "collectors.claude.usage_path must contain `{organization_id}`".to_string(),
+                );
+            }
+            if claude.cookie_host().is_none() {
+                problems.push(
+                    "collectors.claude.cookie_host is required when base_url has no host"
+                        .to_string(),
+                );
+            }
+        }
+        if claude_cookies
+            && !collectors.claude.discover_organizations
+            && collectors.claude.organization_ids().is_empty()
+        {
+            problems.push(
+                "collectors.claude.organization_ids (or CLAUDE_ORGANIZATION_ID) is required unless collectors.claude.discover_organizations is set"
+                    .to_string(),
+            );
+        }
+        if collectors.claude.enabled && collectors.claude.auth == ClaudeAuth::Oauth {
+            match collectors.claude.credentials_path_with(&env) {
+                Some(path) if !path.is_file() => problems.push(format!(
+                    "Claude Code credentials file {} does not exist (run `claude` and log in, or set collectors.claude.credentials_path)",
+                    path.display()
+                )),
+                Some(_) => {}
+                None => problems.push(
+                    "collectors.claude.credentials_path is required when HOME is not set"
+                        .to_string(),
+                ),
+            }
+        }
+        if collectors.claude_code.enabled {
+            match collectors.claude_code.projects_dir_with(&env) {
+                Some(dir) if !dir.is_dir() => problems.push(format!(
+                    "Claude Code transcript directory {} does not exist (set collectors.claude_code.projects_dir)",
+                    dir.display()
+                )),
+                Some(_) => {}
+                None => problems.push(
+                    "collectors.claude_code.projects_dir is required when HOME is not set"
+                        .to_string(),
+                ),
+            }
+        }
+        let admin_keys = [
+            (
+                "claude_api",
+                collectors.claude_api.enabled,
+                &collectors.claude_api.admin_key_env,
+            ),
+            (
+                "claude_code_analytics",
+                collectors.claude_code_analytics.enabled,
+                &collectors.claude_code_analytics.admin_key_env,
+            ),
+        ];
+        for (collector, enabled, key_env) in admin_keys {
+            if enabled && env(key_env).is_none() {
+                problems.push(format!(
+                    "environment variable {key_env} referenced by collectors.{collector}.admin_key_env is not set"
+                ));
+            }
+        }
+        if collectors.openrouter.enabled {
+            let source = if collectors.openrouter.keys.is_empty() {
+                "collectors.openrouter.api_key_env"
+            } else {
+                "collectors.openrouter.keys"
+            };
+            let openrouter = &collectors.openrouter;
+            let provisioned = [
+                ("activity", openrouter.activity),
+                ("list_keys", openrouter.list_keys),
+            ];
+            for (option, enabled) in provisioned {
+                if enabled && openrouter.provisioning_keys.is_empty() {
+                    problems.push(format!(
+                        "collectors.openrouter.{option} requires collectors.openrouter.provisioning_keys"
+                    ));
+                }
+            }
+            if !(openrouter.key_limit_warning > 0.0 && openrouter.key_limit_warning <= 1.0) {
+                problems.push(format!(
+                    "collectors.openrouter.key_limit_warning must be between 0 and 1, got {}",
+                    openrouter.key_limit_warning
+                ));
+            }
+            let mut accounts = BTreeSet::new();
+            for key in &collectors.openrouter.provisioning_keys {
+                if !accounts.insert(&key.account) {
+                    problems.push(format!(
+                        "collectors.openrouter.provisioning_keys has more than one key for account {:?}",
+                        key.account
+                    ));
+                }
+                if env(&key.api_key_env).is_none() {
+                    problems.push(format!(
+                        "environment variable {} referenced by collectors.openrouter.provisioning_keys is not set",
+                        key.api_key_env
+                    ));
+                }
+            }
+            let mut names = BTreeSet::new();
+            for key in collectors.openrouter.keys() {
+                if !names.insert(key.name.clone()) {
+                    problems.push(format!(
+                        "collectors.openrouter.keys has more than one key named {:?}",
+                        key.name
+                    ));
+                }
+                if env(&key.api_key_env).is_none() {
+                    problems.push(format!(
+                        "environment variable {} referenced by {source} is not set",
+                        key.api_key_env
+                    ));
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("Invalid configuration: {}", problems.join("; "));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_from(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config: Config = toml::from_str(
+            r#"
+            mode = "serve"
+
+            [telemetry]
+            service_name = "usage"
+            otlp_enabled = false
+            otlp_endpoint = "http://collector:4317"
+
+            [telemetry.prometheus]
+            enabled = true
+            listen_address = "127.0.0.1:9090"
+
+            [cookiejar]
+            url = "http://cookiejar:50051"
+            connect_timeout = "5s"
+
+            [retry]
+            max_attempts = 5
+            max_backoff = "1m"
+
+            [collectors.claude]
+            organization_id = "org"
+            timeout = "1m"
+            interval = "2m"
+            labels = { team = "platform" }
+
+            [collectors.openrouter]
+            api_key_env = "OR_KEY"
+
+            [collectors.github_copilot]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.mode, Mode::Serve);
+        assert_eq!(config.telemetry.service_name, "usage");
+        assert_eq!(config.telemetry.export_timeout, Duration::from_secs(10));
+        assert!(!config.telemetry.otlp_enabled);
+        assert!(config.telemetry.prometheus.enabled);
+        assert_eq!(
+            config.telemetry.prometheus.listen_address,
+            SocketAddr::from(([127, 0, 0, 1], 9090))
+        );
+        assert_eq!(config.cookiejar.connect_timeout, Duration::from_secs(5));
+        assert_eq!(config.retry.max_attempts, 5);
+        assert_eq!(config.retry.initial_backoff, Duration::from_millis(500));
+        assert_eq!(config.retry.max_backoff, Duration::from_secs(60));
+        assert_eq!(config.collectors.claude.timeout, Duration::from_secs(60));
+        assert_eq!(config.collectors.claude.interval, Duration::from_secs(120));
+        assert_eq!(
+            config.collectors.openrouter.interval,
+            Duration::from_secs(300)
+        );
+        assert_eq!(config.collectors.claude.labels["team"], "platform");
+        assert_eq!(config.collectors.openrouter.api_key_env, "OR_KEY");
+        assert!(!config.collectors.github_copilot.enabled);
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        let result = toml::from_str::<Config>("[collectors.claude]\norg_id = \"x\"\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_file_values() {
+        let mut config = Config::default();
+        config.collectors.claude.organization_id = Some("from-file".to_string());
+        config
+            .apply_env_overrides(env_from(&[
+                ("CLAUDE_ORGANIZATION_ID", "from-env, second"),
+                ("COOKIEJAR_URL", "http://cookiejar:50051"),
+                ("GITHUB_COPILOT_ENABLED", "false"),
+                ("CLAUDE_USAGE_METRICS_MODE", "serve"),
+            ]))
+            .unwrap();
+        assert_eq!(config.mode, Mode::Serve);
+        assert_eq!(
+            config.collectors.claude.organization_ids(),
+            vec!["from-env".to_string(), "second".to_string()]
+        );
+        assert_eq!(
+            config.cookiejar.url.as_deref(),
+            Some("http://cookiejar:50051")
+        );
+        assert!(!config.collectors.github_copilot.enabled);
+    }
+
+    #[test]
+    fn test_invalid_toggle_is_rejected() {
+        let mut config = Config::default();
+        let result = config.apply_env_overrides(env_from(&[("CLAUDE_ENABLED", "yes")]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_all_missing_settings() {
+        let config = Config::default();
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("cookiejar.url"));
+        assert!(err.contains("organization_ids"));
+        assert!(err.contains("OPENROUTER_API_KEY"));
+    }
+
+    #[test]
+    fn test_invalid_mode_is_rejected() {
+        let mut config = Config::default();
+        let result =
+            config.apply_env_overrides(env_from(&[("CLAUDE_USAGE_METRICS_MODE", "daemon")]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "claude", feature = "openrouter", feature = "github-copilot"))]
+    fn test_enable_only_disables_other_collectors() {
+        let mut config = Config::default();
+        config.collectors.github_copilot.enabled = false;
+        config.collectors.enable_only("github_copilot").unwrap();
+        assert!(!config.collectors.claude.enabled);
+        assert!(!config.collectors.openrouter.enabled);
+        assert!(config.collectors.github_copilot.enabled);
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "claude",
+        feature = "claude-code",
+        feature = "admin",
+        feature = "openrouter",
+        feature = "github-copilot"
+    ))]
+    fn test_enable_only_rejects_unknown_collector() {
+        let mut config = Config::default();
+        let err = config
+            .collectors
+            .enable_only("copilot")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("available: claude, claude_code, claude_api, claude_code_analytics, openrouter, github_copilot")
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_interval() {
+        let mut config = Config::default();
+        config.collectors.openrouter.interval = Duration::ZERO;
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("collectors.openrouter.interval must be greater than zero"));
+    }
+
+    #[test]
+    fn test_claude_organization_ids_merge_without_duplicates() {
+        let config: Config = toml::from_str(
+            r#"
+            [collectors.claude]
+            organization_id = "a"
+            organization_ids = ["b", "a", "c"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.collectors.claude.organization_ids(),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_validate_accepts_claude_discovery_without_ids() {
+        let mut config = Config::default();
+        config.cookiejar.url = Some("http://cookiejar:50051".to_string());
+        config.collectors.claude.discover_organizations = true;
+        config.collectors.openrouter.enabled = false;
+        assert!(config.validate_with(env_from(&[])).is_ok());
+    }
+
+    #[test]
+    fn test_claude_oauth_needs_credentials_but_not_cookiejar() {
+        let mut config = Config::default();
+        config.collectors.claude.auth = ClaudeAuth::Oauth;
+        config.collectors.openrouter.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+
+        let err = config
+            .validate_with(env_from(&[("HOME", "/nonexistent")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/nonexistent/.claude/.credentials.json does not exist"));
+        assert!(!err.contains("cookiejar.url"));
+        assert!(!err.contains("organization_ids"));
+
+        config.collectors.claude.credentials_path = Some(PathBuf::from("Cargo.toml"));
+        assert!(config.validate_with(env_from(&[])).is_ok());
+
+        // The cookie endpoints are not used, the OAuth ones are checked.
+        let claude = &mut config.collectors.claude;
+        claude.usage_path = "/api/usage".to_string();
+        claude.oauth_base_url = "127.0.0.1:8080".to_string();
+        claude.oauth_usage_path = "api/oauth/usage".to_string();
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(!err.contains("collectors.claude.usage_path"));
+        assert!(err.contains("collectors.claude.oauth_base_url must be an http(s) URL"));
+        assert!(err.contains("collectors.claude.oauth_usage_path must start with `/`"));
+    }
+
+    #[test]
+    fn test_claude_credentials_path_prefers_config_dir() {
+        let config = ClaudeConfig::default();
+        assert_eq!(
+            config.credentials_path_with(env_from(&[
+                ("HOME", "/home/me"),
+                ("CLAUDE_CONFIG_DIR", "/etc/claude"),
+            ])),
+            Some(PathBuf::from("/etc/claude/.credentials.json"))
+        );
+        assert_eq!(
+            config.credentials_path_with(env_from(&[("HOME", "/home/me")])),
+            Some(PathBuf::from("/home/me/.claude/.credentials.json"))
+        );
+        assert_eq!(config.credentials_path_with(env_from(&[])), None);
+    }
+
+    #[test]
+    fn test_claude_endpoints_follow_base_url() {
+        let mut config = Config::default();
+        config.cookiejar.url = Some("http://cookiejar:50051".to_string());
+        config.collectors.claude.organization_id = Some("org".to_string());
+        config.collectors.openrouter.enabled = false;
+        config
+            .apply_env_overrides(env_from(&[("CLAUDE_BASE_URL", "http://127.0.0.1:8080")]))
+            .unwrap();
+        let claude = &mut config.collectors.claude;
+        assert_eq!(claude.cookie_host().as_deref(), Some(".127.0.0.1"));
+        claude.cookie_host = Some(".claude.example.com".to_string());
+        assert_eq!(claude.cookie_host().as_deref(), Some(".claude.example.com"));
+        assert!(config.validate_with(env_from(&[])).is_ok());
+
+        let claude = &mut config.collectors.claude;
+        claude.base_url = "claude.example.com".to_string();
+        claude.usage_path = "/api/usage".to_string();
+        claude.organizations_path = "api/organizations".to_string();
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("collectors.claude.base_url must be an http(s) URL"));
+        assert!(err.contains("usage_path must contain `{organization_id}`"));
+        assert!(err.contains("collectors.claude.organizations_path must start with `/`"));
+    }
+
+    #[test]
+    fn test_claude_code_needs_transcript_directory() {
+        let mut config = Config::default();
+        config.collectors.claude.enabled = false;
+        config.collectors.openrouter.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+        config.collectors.claude_code.enabled = true;
+
+        let err = config
+            .validate_with(env_from(&[("CLAUDE_CONFIG_DIR", "/nonexistent")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/nonexistent/projects does not exist"));
+
+        config.collectors.claude_code.projects_dir = Some(PathBuf::from("src"));
+        assert!(config.validate_with(env_from(&[])).is_ok());
+    }
+
+    #[test]
+    fn test_claude_api_needs_admin_key_and_http_base_url() {
+        let mut config = Config::default();
+        config.collectors.claude.enabled = false;
+        config.collectors.openrouter.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+        config.collectors.claude_api.enabled = true;
+        config.collectors.claude_api.base_url = "localhost:8080".to_string();
+
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(
+            err.contains("ANTHROPIC_ADMIN_KEY referenced by collectors.claude_api.admin_key_env")
+        );
+        assert!(err.contains("collectors.claude_api.base_url must be an http(s) URL"));
+
+        config.collectors.claude_api.base_url = "http://127.0.0.1:8080".to_string();
+        assert!(
+            config
+                .validate_with(env_from(&[("ANTHROPIC_ADMIN_KEY", "sk-ant-admin01-test")]))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_openrouter_keys_replace_api_key_env() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [collectors.claude]
+            enabled = false
+
+            [collectors.github_copilot]
+            enabled = false
+
+            [[collectors.openrouter.keys]]
+            name = "team-a"
+            api_key_env = "OR_TEAM_A"
+            account = "acme"
+
+            [[collectors.openrouter.keys]]
+            name = "team-a"
+            api_key_env = "OR_TEAM_B"
+            "#,
+        )
+        .unwrap();
+        let keys = config.collectors.openrouter.keys();
+        assert_eq!(keys[0].account(), "acme");
+        assert_eq!(keys[1].account(), "team-a");
+
+        let err = config
+            .validate_with(env_from(&[("OR_TEAM_A", "sk-or-v1-a")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("more than one key named \"team-a\""));
+        assert!(err.contains("OR_TEAM_B referenced by collectors.openrouter.keys is not set"));
+        assert!(!err.contains("OPENROUTER_API_KEY"));
+
+        config.collectors.openrouter.keys.clear();
+        let keys = config.collectors.openrouter.keys();
+        assert_eq!(keys[0].name, "default");
+        assert_eq!(keys[0].api_key_env, "OPENROUTER_API_KEY");
+    }
+
+    #[test]
+    fn test_openrouter_activity_needs_provisioning_key() {
+        let mut config = Config::default();
+        config.collectors.claude.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+        config.collectors.openrouter.activity = true;
+        let env = env_from(&[("OPENROUTER_API_KEY", "sk-or-v1-test")]);
+
+        let err = config.validate_with(&env).unwrap_err().to_string();
+        assert!(err.contains("activity requires collectors.openrouter.provisioning_keys"));
+
+        let provisioning: OpenRouterProvisioningKeyConfig =
+            toml::from_str(r#"api_key_env = "OR_PROVISIONING""#).unwrap();
+        assert_eq!(provisioning.account, "default");
+        config.collectors.openrouter.provisioning_keys = vec![provisioning];
+        let err = config.validate_with(&env).unwrap_err().to_string();
+        assert!(
+            err.contains("OR_PROVISIONING referenced by collectors.openrouter.provisioning_keys")
+        );
+
+        let env = env_from(&[
+            ("OPENROUTER_API_KEY", "sk-or-v1-test"),
+            ("OR_PROVISIONING", "sk-or-v1-prov"),
+        ]);
+        assert!(config.validate_with(env).is_ok());
+    }
+
+    #[test]
+    fn test_openrouter_key_listing_replaces_api_key_env() {
+        let mut config = Config::default();
+        config.collectors.claude.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+        let openrouter = &mut config.collectors.openrouter;
+        openrouter.list_keys = true;
+        openrouter.key_limit_warning = 1.5;
+        assert!(openrouter.keys().is_empty());
+
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("list_keys requires collectors.openrouter.provisioning_keys"));
+        assert!(err.contains("key_limit_warning must be between 0 and 1, got 1.5"));
+        assert!(!err.contains("OPENROUTER_API_KEY"));
+
+        let openrouter = &mut config.collectors.openrouter;
+        openrouter.key_limit_warning = 0.8;
+        openrouter.provisioning_keys = vec![OpenRouterProvisioningKeyConfig {
+            account: "default".to_string(),
+            api_key_env: "OR_PROVISIONING".to_string(),
+        }];
+        assert!(
+            config
+                .validate_with(env_from(&[("OR_PROVISIONING", "sk-or-v1-prov")]))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_retry_policy() {
+        let mut config = Config::default();
+        config.retry.max_attempts = 0;
+        config.retry.initial_backoff = Duration::from_secs(60);
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("retry.max_attempts must be at least 1"));
+        assert!(err.contains("retry.initial_backoff must not exceed retry.max_backoff"));
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_endpoint() {
+        let mut config = Config::default();
+        config.cookiejar.url = Some("cookiejar:50051".to_string());
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("cookiejar.url must be an http(s) URL"));
+    }
+
+    #[test]
+    fn test_otlp_endpoint_is_only_validated_when_enabled() {
+        let mut config = Config::default();
+        config.telemetry.otlp_enabled = false;
+        config.telemetry.otlp_endpoint = "collector:4317".to_string();
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(!err.contains("telemetry.otlp_endpoint"));
+
+        config.telemetry.otlp_enabled = true;
+        let err = config.validate_with(env_from(&[])).unwrap_err().to_string();
+        assert!(err.contains("telemetry.otlp_endpoint must be an http(s) URL"));
+    }
+
+    #[test]
+    fn test_validate_ignores_disabled_collectors() {
+        let mut config = Config::default();
+        config.collectors.claude.enabled = false;
+        config.collectors.github_copilot.enabled = false;
+        assert!(
+            config
+                .validate_with(env_from(&[("OPENROUTER_API_KEY", "sk-or-v1-test")]))
+                .is_ok()
+        );
+    }
+}
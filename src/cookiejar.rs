@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::config::CookiejarConfig;
+use crate::proto::cookiejar::v1::{
+    GetCookiesRequest, GetCookiesResponse, cookie_service_client::CookieServiceClient,
+};
+use crate::retry::RetryPolicy;
+
+/// User agent sent alongside borrowed browser cookies.
+pub const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// Client for the cookiejar-reader gRPC service.
+#[derive(Debug, Clone)]
+pub struct CookieJar {
+    url: Option<String>,
+    connect_timeout: Duration,
+    retry: RetryPolicy,
+}
+
+impl CookieJar {
+    pub fn new(config: &CookiejarConfig) -> Self {
+        Self {
+            url: config.url.clone(),
+            connect_timeout: config.connect_timeout,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Replaces the default retry policy for cookie lookups.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fetches the `Cookie` header value for `host`.
+    pub async fn fetch_cookies(&self, host: &str) -> anyhow::Result<String> {
+        self.retry
+            .run("cookiejar.get_cookies", || self.try_fetch_cookies(host))
+            .await
+    }
+
+    async fn try_fetch_cookies(&self, host: &str) -> anyhow::Result<String> {
+        let endpoint = self
+            .url
+            .clone()
+            .context("cookiejar.url is not configured")?;
+        let channel = tonic::transport::Channel::from_shared(endpoint.into_bytes())
+            .context("Invalid cookiejar URL")?
+            .connect_timeout(self.connect_timeout)
+            .connect()
+            .await
+            .context("Failed to connect to cookie service")?;
+        let mut client = CookieServiceClient::new(channel);
+
+        let request = GetCookiesRequest {
+            host: host.to_string(),
+        };
+        let response: tonic::Response<GetCookiesResponse> = client
+            .get_cookies(request)
+            .await
+            .context("Failed to get cookies")?;
+
+        Ok(response.into_inner().cookies)
+    }
+}
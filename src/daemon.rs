@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use claude_usage_metrics::collector::{self, Registry};
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::telemetry::Recorder;
+
+// ============================================================================
+// Daemon Mode
+// ============================================================================
+
+/// Polls every enabled collector on its own interval until a shutdown signal
+/// is received. Failed runs are logged and retried on the next tick.
+pub async fn serve(registry: &Registry, recorder: Arc<Recorder>) -> anyhow::Result<()> {
+    let mut tasks = JoinSet::new();
+    for collector in registry.enabled() {
+        let collector = collector.clone();
+        let recorder = recorder.clone();
+        let interval = collector.interval();
+        info!(
+            collector = collector.name(),
+            interval = ?interval,
+            "Scheduling collector"
+        );
+
+        tasks.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let outcome = collector::run_collector(collector.clone()).await;
+                // Failures are already logged; keep polling.
+                let _ = recorder.record_result(outcome);
+            }
+        });
+    }
+
+    if tasks.is_empty() {
+        anyhow::bail!("No collectors are enabled");
+    }
+
+    shutdown_signal().await?;
+    info!("Shutdown signal received, stopping collectors");
+    tasks.abort_all();
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result
+            && !e.is_cancelled()
+        {
+            warn!(error = %e, "Collector task ended unexpectedly");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn shutdown_signal() -> anyhow::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() -> anyhow::Result<()> {
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
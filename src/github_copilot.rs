@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::collector::{Collector, Descriptor, Sample};
+use crate::config::GithubCopilotConfig;
+use crate::cookiejar::{self, CookieJar};
+use crate::pace::{Pace, PaceTracker};
+use crate::retry::{self, RetryPolicy};
+
+// ============================================================================
+// GitHub Copilot Types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct GithubCopilotQuotaRemaining {
+    #[serde(rename = "chatPercentage")]
+    chat_percentage: f64,
+    #[serde(rename = "premiumInteractionsPercentage")]
+    premium_interactions_percentage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubCopilotQuotas {
+    remaining: GithubCopilotQuotaRemaining,
+    #[serde(rename = "resetDate")]
+    reset_date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubCopilotResponse {
+    quotas: GithubCopilotQuotas,
+}
+
+/// GitHub Copilot premium and chat quota consumption.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GithubCopilotQuota {
+    /// Share of the chat quota consumed, from 0.0 to 1.0.
+    pub chat_utilization: f64,
+    /// Share of the premium interactions quota consumed, from 0.0 to 1.0.
+    pub premium_interactions_utilization: f64,
+    pub reset_date: Option<NaiveDate>,
+    pub seconds_to_reset: Option<i64>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl GithubCopilotQuota {
+    fn from_quotas(quotas: GithubCopilotQuotas, now: DateTime<Utc>) -> Self {
+        let reset_date = NaiveDate::parse_from_str(&quotas.reset_date, "%Y-%m-%d").ok();
+        let seconds_to_reset = reset_date.and_then(|d| d.and_hms_opt(0, 0, 0)).map(|dt| {
+            let reset_utc = dt.and_utc();
+            (reset_utc - now).num_seconds().max(0)
+        });
+
+        Self {
+            chat_utilization: 1.0 - quotas.remaining.chat_percentage / 100.0,
+            premium_interactions_utilization: 1.0
+                - quotas.remaining.premium_interactions_percentage / 100.0,
+            reset_date,
+            seconds_to_reset,
+            fetched_at: now,
+        }
+    }
+}
+
+// ============================================================================
+// GitHub Copilot Client
+// ============================================================================
+
+/// Fetches GitHub Copilot quotas with browser cookies borrowed from
+/// cookiejar-reader.
+#[derive(Debug, Clone)]
+pub struct GithubCopilotClient {
+    cookiejar: CookieJar,
+    http_client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl GithubCopilotClient {
+    pub fn new(cookiejar: CookieJar, timeout: Duration) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            cookiejar,
+            http_client,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Replaces the default retry policy for API requests.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub async fn fetch_quota(&self) -> anyhow::Result<GithubCopilotQuota> {
+        let cookies = self.cookiejar.fetch_cookies("github.com").await?;
+
+        let quota_response = self
+            .retry
+            .run("github_copilot.quota", || async {
+                let request = self
+                    .http_client
+                    .get("https://github.com/github-copilot/chat")
+                    .header("Cookie", &cookies)
+                    .header("User-Agent", cookiejar::BROWSER_USER_AGENT)
+                    .header("github-verified-fetch", "true")
+                    .header("x-requested-with", "XMLHttpRequest")
+                    .header("accept", "application/json");
+                retry::send(request, "GitHub Copilot API")
+                    .await?
+                    .json::<GithubCopilotResponse>()
+                    .await
+                    .context("Failed to parse GitHub Copilot quota response")
+            })
+            .await?;
+
+        Ok(GithubCopilotQuota::from_quotas(
+            quota_response.quotas,
+            Utc::now(),
+        ))
+    }
+}
+
+// ============================================================================
+// GitHub Copilot Metrics Collection
+// ============================================================================
+
+static UTILIZATION: Descriptor = Descriptor {
+    name: "github_copilot.usage.utilization",
+    description: "GitHub Copilot usage utilization rate",
+    unit: "ratio",
+};
+
+static SECONDS_TO_RESET: Descriptor = Descriptor {
+    name: "github_copilot.usage.seconds_to_reset",
+    description: "Seconds until GitHub Copilot quota resets",
+    unit: "s",
+};
+
+static PACE: Descriptor = Descriptor {
+    name: "github_copilot.usage.pace",
+    description: "Utilization divided by the elapsed fraction of the monthly quota period",
+    unit: "1",
+};
+
+static PROJECTED_UTILIZATION: Descriptor = Descriptor {
+    name: "github_copilot.usage.projected_utilization",
+    description: "Utilization expected when the GitHub Copilot quota resets, at the recent rate",
+    unit: "ratio",
+};
+
+pub struct GithubCopilotCollector {
+    config: GithubCopilotConfig,
+    client: GithubCopilotClient,
+    pace: Mutex<PaceTracker>,
+}
+
+impl GithubCopilotCollector {
+    pub fn new(
+        config: GithubCopilotConfig,
+        cookiejar: CookieJar,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Self> {
+        let client = GithubCopilotClient::new(cookiejar, config.timeout)?.with_retry(retry);
+        Ok(Self {
+            config,
+            client,
+            pace: Mutex::default(),
+        })
+    }
+
+    /// Pace of a quota at `now` over the month ending at its reset date.
+    fn pace(
+        &self,
+        metric_name: &str,
+        utilization: f64,
+        reset_date: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Option<Pace> {
+        let resets_at = reset_date.and_hms_opt(0, 0, 0)?.and_utc();
+        let starts_at = resets_at.checked_sub_months(Months::new(1))?;
+        self.pace
+            .lock()
+            .unwrap()
+            .observe(metric_name, utilization, starts_at, resets_at, now)
+    }
+}
+
+#[async_trait]
+impl Collector for GithubCopilotCollector {
+    fn name(&self) -> &'static str {
+        "github_copilot"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub Copilot"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "github-copilot-quota"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "github_copilot_quota_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Fetching GitHub Copilot quota");
+
+        let quota = self.client.fetch_quota().await?;
+
+        let utilizations = [
+            ("chat", quota.chat_utilization),
+            (
+                "premium_interactions",
+                quota.premium_interactions_utilization,
+            ),
+        ];
+        let mut samples = Vec::new();
+        for (metric_name, utilization) in utilizations {
+            samples.push(
+                Sample::f64(&UTILIZATION, utilization).with_attribute("metric_name", metric_name),
+            );
+            if let Some(reset_date) = quota.reset_date
+                && let Some(pace) =
+                    self.pace(metric_name, utilization, reset_date, quota.fetched_at)
+            {
+                samples
+                    .push(Sample::f64(&PACE, pace.pace).with_attribute("metric_name", metric_name));
+                samples.push(
+                    Sample::f64(&PROJECTED_UTILIZATION, pace.projected_utilization)
+                        .with_attribute("metric_name", metric_name),
+                );
+            }
+        }
+        if let Some(seconds) = quota.seconds_to_reset {
+            samples.push(Sample::i64(&SECONDS_TO_RESET, seconds));
+        }
+
+        info!(
+            chat_utilization = %quota.chat_utilization,
+            premium_interactions_utilization = %quota.premium_interactions_utilization,
+            seconds_to_reset = ?quota.seconds_to_reset,
+            "Fetched GitHub Copilot usage metrics"
+        );
+
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotas(reset_date: &str) -> GithubCopilotQuotas {
+        GithubCopilotQuotas {
+            remaining: GithubCopilotQuotaRemaining {
+                chat_percentage: 100.0,
+                premium_interactions_percentage: 25.0,
+            },
+            reset_date: reset_date.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_quota_converts_remaining_to_utilization() {
+        let now = "2026-01-31T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let quota = GithubCopilotQuota::from_quotas(quotas("2026-02-01"), now);
+        assert_eq!(quota.chat_utilization, 0.0);
+        assert_eq!(quota.premium_interactions_utilization, 0.75);
+        assert_eq!(quota.reset_date, NaiveDate::from_ymd_opt(2026, 2, 1));
+        assert_eq!(quota.seconds_to_reset, Some(86400));
+    }
+
+    #[test]
+    fn test_pace_over_the_month_before_the_reset() {
+        let collector = GithubCopilotCollector::new(
+            GithubCopilotConfig::default(),
+            CookieJar::new(&crate::config::CookiejarConfig::default()),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        // The month before March 31 starts on February 28.
+        let reset_date = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let now = "2026-03-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let pace = collector
+            .pace("premium_interactions", 0.25, reset_date, now)
+            .unwrap();
+        assert_eq!(pace.pace, 0.5);
+        assert_eq!(pace.projected_utilization, 0.5);
+
+        // Four days later the recent rate of 0.05 a day takes over for the
+        // remaining 11.5 days.
+        let later = now + chrono::TimeDelta::days(4);
+        let pace = collector
+            .pace("premium_interactions", 0.45, reset_date, later)
+            .unwrap();
+        assert!((pace.pace - 0.45 / (19.5 / 31.0)).abs() < 1e-9);
+        assert!((pace.projected_utilization - 1.025).abs() < 1e-9);
+
+        // After the reset date the old window no longer applies.
+        let after = "2026-03-31T00:00:01Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(
+            collector
+                .pace("premium_interactions", 0.45, reset_date, after)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_reset_date_returns_none() {
+        let quota = GithubCopilotQuota::from_quotas(quotas("soon"), Utc::now());
+        assert!(quota.reset_date.is_none());
+        assert!(quota.seconds_to_reset.is_none());
+    }
+}
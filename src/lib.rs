@@ -0,0 +1,39 @@
+//! Fetches Claude, OpenRouter and GitHub Copilot usage as typed snapshots.
+//!
+//! Provider clients such as [`claude::ClaudeClient`] return plain Rust types
+//! and do not depend on OpenTelemetry. The [`collector`] module adapts them to
+//! exporter-independent samples, which the `claude-usage-metrics` binary
+//! records and exports.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use std::time::Duration;
+//!
+//! use claude_usage_metrics::openrouter::OpenRouterClient;
+//!
+//! let client = OpenRouterClient::new(Duration::from_secs(30))?;
+//! let credits = client.fetch_credits("sk-or-v1-...").await?;
+//! println!("{} USD remaining", credits.remaining);
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(feature = "claude")]
+pub mod claude;
+#[cfg(feature = "claude-code")]
+pub mod claude_code;
+pub mod collector;
+pub mod config;
+#[cfg(feature = "cookiejar")]
+pub mod cookiejar;
+#[cfg(feature = "github-copilot")]
+pub mod github_copilot;
+#[cfg(feature = "openrouter")]
+pub mod openrouter;
+pub mod pace;
+#[cfg(feature = "cookiejar")]
+pub mod proto;
+pub mod retry;
+pub mod state;
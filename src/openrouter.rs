@@ -0,0 +1,1506 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+use crate::collector::{Collector, Descriptor, Sample, Value, into_increments};
+use crate::config::{OpenRouterConfig, OpenRouterKeyConfig, OpenRouterProvisioningKeyConfig};
+use crate::retry::{self, RetryPolicy};
+use crate::state::StateStore;
+
+// ============================================================================
+// OpenRouter Types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterCreditsData {
+    total_credits: f64,
+    total_usage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterCreditsResponse {
+    data: OpenRouterCreditsData,
+}
+
+/// Account-wide OpenRouter credit balance, in USD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenRouterMetrics {
+    pub total_credits: f64,
+    pub total_usage: f64,
+    pub remaining: f64,
+}
+
+impl From<OpenRouterCreditsResponse> for OpenRouterMetrics {
+    fn from(response: OpenRouterCreditsResponse) -> Self {
+        Self {
+            total_credits: response.data.total_credits,
+            total_usage: response.data.total_usage,
+            remaining: response.data.total_credits - response.data.total_usage,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterKeyData {
+    label: String,
+    limit: Option<f64>,
+    usage: f64,
+    limit_remaining: Option<f64>,
+    limit_reset: Option<String>,
+    #[serde(default)]
+    is_free_tier: bool,
+    rate_limit: Option<OpenRouterRateLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterKeyResponse {
+    data: OpenRouterKeyData,
+}
+
+/// Request rate limit of an API key.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OpenRouterRateLimit {
+    pub requests: i64,
+    /// Window the requests are counted over, e.g. `10s`.
+    pub interval: String,
+}
+
+/// Spend limit and usage of the API key making the request, in USD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenRouterKey {
+    pub label: String,
+    /// Spend cap, or `None` when the key is unlimited.
+    pub limit: Option<f64>,
+    pub usage: f64,
+    pub limit_remaining: Option<f64>,
+    /// How often the limit resets (e.g. `monthly`), or `None` if it never does.
+    pub limit_reset: Option<String>,
+    pub is_free_tier: bool,
+    pub rate_limit: Option<OpenRouterRateLimit>,
+}
+
+impl From<OpenRouterKeyResponse> for OpenRouterKey {
+    fn from(response: OpenRouterKeyResponse) -> Self {
+        let data = response.data;
+        Self {
+            label: data.label,
+            limit: data.limit,
+            usage: data.usage,
+            limit_remaining: data.limit_remaining,
+            limit_reset: data.limit_reset,
+            is_free_tier: data.is_free_tier,
+            rate_limit: data.rate_limit,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterApiKeyData {
+    hash: String,
+    name: String,
+    label: String,
+    #[serde(default)]
+    disabled: bool,
+    limit: Option<f64>,
+    limit_remaining: Option<f64>,
+    limit_reset: Option<String>,
+    usage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterApiKeysResponse {
+    data: Vec<OpenRouterApiKeyData>,
+}
+
+/// An API key of the account, as listed with a provisioning key. Amounts
+/// are in USD.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenRouterApiKey {
+    /// Stable identifier of the key; the key itself is never returned.
+    pub hash: String,
+    pub name: String,
+    pub label: String,
+    pub disabled: bool,
+    /// Spend cap, or `None` when the key is unlimited.
+    pub limit: Option<f64>,
+    pub limit_remaining: Option<f64>,
+    /// How often the limit resets (e.g. `monthly`), or `None` if it never does.
+    pub limit_reset: Option<String>,
+    pub usage: f64,
+}
+
+impl From<OpenRouterApiKeyData> for OpenRouterApiKey {
+    fn from(data: OpenRouterApiKeyData) -> Self {
+        Self {
+            hash: data.hash,
+            name: data.name,
+            label: data.label,
+            disabled: data.disabled,
+            limit: data.limit,
+            limit_remaining: data.limit_remaining,
+            limit_reset: data.limit_reset,
+            usage: data.usage,
+        }
+    }
+}
+
+impl OpenRouterApiKey {
+    /// Share of the limit spent in the current limit period, or `None` for
+    /// unlimited keys.
+    pub fn limit_used(&self) -> Option<f64> {
+        let limit = self.limit?;
+        if limit <= 0.0 {
+            return Some(1.0);
+        }
+        let spent = match self.limit_remaining {
+            Some(remaining) => limit - remaining,
+            None => self.usage,
+        };
+        Some(spent / limit)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterActivityItem {
+    date: String,
+    model: String,
+    provider_name: String,
+    usage: f64,
+    requests: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    reasoning_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterActivityResponse {
+    data: Vec<OpenRouterActivityItem>,
+}
+
+/// Requests, tokens and spend of one model at one provider on one completed
+/// UTC day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenRouterActivity {
+    /// UTC day, e.g. `2026-01-31`.
+    pub date: String,
+    pub model: String,
+    pub provider: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub reasoning_tokens: u64,
+    /// Spend in USD.
+    pub usage: f64,
+}
+
+/// Sums the per-endpoint activity rows by day, model and provider.
+fn sum_activity(items: Vec<OpenRouterActivityItem>) -> Vec<OpenRouterActivity> {
+    let mut activity: BTreeMap<(String, String, String), OpenRouterActivity> = BTreeMap::new();
+    for item in items {
+        let key = (item.date, item.model, item.provider_name);
+        let entry = activity
+            .entry(key.clone())
+            .or_insert_with(|| OpenRouterActivity {
+                date: key.0,
+                model: key.1,
+                provider: key.2,
+                ..OpenRouterActivity::default()
+            });
+        entry.requests += item.requests;
+        entry.prompt_tokens += item.prompt_tokens;
+        entry.completion_tokens += item.completion_tokens;
+        entry.reasoning_tokens += item.reasoning_tokens;
+        entry.usage += item.usage;
+    }
+    activity.into_values().collect()
+}
+
+// ============================================================================
+// OpenRouter Client
+// ============================================================================
+
+const API_BASE_URL: &str = "https://openrouter.ai/api/v1";
+
+/// Fetches OpenRouter account information with an API key.
+#[derive(Debug, Clone)]
+pub struct OpenRouterClient {
+    http_client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl OpenRouterClient {
+    pub fn new(timeout: Duration) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            http_client,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Replaces the default retry policy for API requests.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub async fn fetch_credits(&self, api_key: &str) -> anyhow::Result<OpenRouterMetrics> {
+        let response = self
+            .retry
+            .run("openrouter.credits", || async {
+                let request = self
+                    .http_client
+                    .get(format!("{API_BASE_URL}/credits"))
+                    .header("Authorization", format!("Bearer {}", api_key));
+                retry::send(request, "OpenRouter API")
+                    .await?
+                    .json::<OpenRouterCreditsResponse>()
+                    .await
+                    .context("Failed to parse OpenRouter credits response")
+            })
+            .await?;
+
+        Ok(response.into())
+    }
+
+    /// Describes the key itself: its label, spend limit and rate limit.
+    pub async fn fetch_key(&self, api_key: &str) -> anyhow::Result<OpenRouterKey> {
+        let response = self
+            .retry
+            .run("openrouter.key", || async {
+                let request = self
+                    .http_client
+                    .get(format!("{API_BASE_URL}/key"))
+                    .header("Authorization", format!("Bearer {}", api_key));
+                retry::send(request, "OpenRouter API")
+                    .await?
+                    .json::<OpenRouterKeyResponse>()
+                    .await
+                    .context("Failed to parse OpenRouter key response")
+            })
+            .await?;
+
+        Ok(response.into())
+    }
+
+    /// Lists every API key of the account, including disabled ones. Needs a
+    /// provisioning key.
+    pub async fn fetch_keys(
+        &self,
+        provisioning_key: &str,
+    ) -> anyhow::Result<Vec<OpenRouterApiKey>> {
+        let mut keys: Vec<OpenRouterApiKey> = Vec::new();
+        loop {
+            let offset = keys.len().to_string();
+            let url = reqwest::Url::parse_with_params(
+                &format!("{API_BASE_URL}/keys"),
+                [("include_disabled", "true"), ("offset", offset.as_str())],
+            )?;
+            let response = self
+                .retry
+                .run("openrouter.keys", || async {
+                    let request = self
+                        .http_client
+                        .get(url.clone())
+                        .header("Authorization", format!("Bearer {}", provisioning_key));
+                    retry::send(request, "OpenRouter API")
+                        .await?
+                        .json::<OpenRouterApiKeysResponse>()
+                        .await
+                        .context("Failed to parse OpenRouter keys response")
+                })
+                .await?;
+
+            // An empty page ends the listing; a repeated one means the
+            // offset was ignored.
+            let repeated = response
+                .data
+                .first()
+                .is_some_and(|first| keys.iter().any(|key| key.hash == first.hash));
+            if response.data.is_empty() || repeated {
+                return Ok(keys);
+            }
+            keys.extend(response.data.into_iter().map(OpenRouterApiKey::from));
+        }
+    }
+
+    /// Activity of the last 30 completed UTC days, by day, model and
+    /// provider. Needs a provisioning key.
+    pub async fn fetch_activity(
+        &self,
+        provisioning_key: &str,
+    ) -> anyhow::Result<Vec<OpenRouterActivity>> {
+        let response = self
+            .retry
+            .run("openrouter.activity", || async {
+                let request = self
+                    .http_client
+                    .get(format!("{API_BASE_URL}/activity"))
+                    .header("Authorization", format!("Bearer {}", provisioning_key));
+                retry::send(request, "OpenRouter API")
+                    .await?
+                    .json::<OpenRouterActivityResponse>()
+                    .await
+                    .context("Failed to parse OpenRouter activity response")
+            })
+            .await?;
+
+        Ok(sum_activity(response.data))
+    }
+}
+
+// ============================================================================
+// OpenRouter Metrics Collection
+// ============================================================================
+
+static CREDITS_TOTAL: Descriptor = Descriptor {
+    name: "openrouter.credits.total",
+    description: "Total OpenRouter credits purchased",
+    unit: "USD",
+};
+
+static CREDITS_USAGE: Descriptor = Descriptor {
+    name: "openrouter.credits.usage",
+    description: "Total OpenRouter credits used",
+    unit: "USD",
+};
+
+static CREDITS_REMAINING: Descriptor = Descriptor {
+    name: "openrouter.credits.remaining",
+    description: "Remaining OpenRouter credits",
+    unit: "USD",
+};
+
+static CREDITS_SPENT: Descriptor = Descriptor {
+    name: "openrouter.credits.spent",
+    description: "OpenRouter credits spent since the previous run",
+    unit: "USD",
+};
+
+static CREDITS_RECENT_SPEND: Descriptor = Descriptor {
+    name: "openrouter.credits.recent_spend",
+    description: "OpenRouter credits spent over the trailing window",
+    unit: "USD",
+};
+
+static CREDITS_PURCHASES: Descriptor = Descriptor {
+    name: "openrouter.credits.purchases",
+    description: "OpenRouter credit purchases seen as a rise in total credits",
+    unit: "{purchase}",
+};
+
+static CREDITS_PURCHASED: Descriptor = Descriptor {
+    name: "openrouter.credits.purchased",
+    description: "OpenRouter credits purchased since the previous run",
+    unit: "USD",
+};
+
+static KEY_LIMIT: Descriptor = Descriptor {
+    name: "openrouter.key.limit",
+    description: "Spend limit of the OpenRouter API key",
+    unit: "USD",
+};
+
+static KEY_USAGE: Descriptor = Descriptor {
+    name: "openrouter.key.usage",
+    description: "Credits used by the OpenRouter API key",
+    unit: "USD",
+};
+
+static KEY_LIMIT_REMAINING: Descriptor = Descriptor {
+    name: "openrouter.key.limit_remaining",
+    description: "Credits the OpenRouter API key can still spend before its limit",
+    unit: "USD",
+};
+
+static KEY_IS_FREE_TIER: Descriptor = Descriptor {
+    name: "openrouter.key.is_free_tier",
+    description: "Whether the OpenRouter account is on the free tier (1) or has paid (0)",
+    unit: "1",
+};
+
+static KEY_RATE_LIMIT: Descriptor = Descriptor {
+    name: "openrouter.key.rate_limit",
+    description: "Requests the OpenRouter API key may make per `interval`",
+    unit: "{request}",
+};
+
+static LISTED_KEY_USAGE: Descriptor = Descriptor {
+    name: "openrouter.keys.usage",
+    description: "Credits used by each API key of the OpenRouter account",
+    unit: "USD",
+};
+
+static LISTED_KEY_LIMIT: Descriptor = Descriptor {
+    name: "openrouter.keys.limit",
+    description: "Spend limit of each API key of the OpenRouter account",
+    unit: "USD",
+};
+
+static LISTED_KEY_LIMIT_REMAINING: Descriptor = Descriptor {
+    name: "openrouter.keys.limit_remaining",
+    description: "Credits each API key can still spend before its limit",
+    unit: "USD",
+};
+
+static LISTED_KEY_DISABLED: Descriptor = Descriptor {
+    name: "openrouter.keys.disabled",
+    description: "Whether the API key is disabled (1) or active (0)",
+    unit: "1",
+};
+
+static LISTED_KEY_NEAR_LIMIT: Descriptor = Descriptor {
+    name: "openrouter.keys.near_limit",
+    description: "Whether the API key has spent at least `key_limit_warning` of its limit",
+    unit: "1",
+};
+
+static LISTED_KEY_OVER_LIMIT: Descriptor = Descriptor {
+    name: "openrouter.keys.over_limit",
+    description: "Whether the API key has no limit left to spend",
+    unit: "1",
+};
+
+static ACTIVITY_REQUESTS: Descriptor = Descriptor {
+    name: "openrouter.activity.requests",
+    description: "OpenRouter requests by model and provider",
+    unit: "{request}",
+};
+
+static ACTIVITY_TOKENS: Descriptor = Descriptor {
+    name: "openrouter.activity.tokens",
+    description: "OpenRouter tokens by model, provider and type",
+    unit: "{token}",
+};
+
+static ACTIVITY_SPEND: Descriptor = Descriptor {
+    name: "openrouter.activity.spend",
+    description: "OpenRouter spend by model and provider",
+    unit: "USD",
+};
+
+/// Name of the activity state in the state store.
+const ACTIVITY_STATE_NAME: &str = "openrouter_activity";
+
+/// OpenRouter returns the last 30 completed days; totals reported for older
+/// days are forgotten.
+const ACTIVITY_HISTORY_DAYS: i64 = 31;
+
+/// Totals already reported per day (`YYYY-MM-DD`) and series.
+type DailyTotals = BTreeMap<String, BTreeMap<String, f64>>;
+
+/// Daily totals by account. An account missing here has never been
+/// collected.
+type ReportedActivity = BTreeMap<String, DailyTotals>;
+
+/// Name of the credit snapshot state in the state store.
+const CREDITS_STATE_NAME: &str = "openrouter_credits";
+
+/// Trailing windows of `openrouter.credits.recent_spend`, by `window`
+/// attribute. The last one is the longest.
+const SPEND_WINDOWS: [(&str, TimeDelta); 3] = [
+    ("1h", TimeDelta::hours(1)),
+    ("1d", TimeDelta::days(1)),
+    ("1w", TimeDelta::weeks(1)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct CreditSnapshot {
+    at: DateTime<Utc>,
+    total_credits: f64,
+    total_usage: f64,
+}
+
+/// Change in the balance of an account since its previous snapshot.
+#[derive(Debug, Default, PartialEq)]
+struct CreditChange {
+    spent: f64,
+    purchased: f64,
+}
+
+/// Balance snapshots by account, reaching back just past the longest spend
+/// window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CreditHistory {
+    accounts: BTreeMap<String, Vec<CreditSnapshot>>,
+}
+
+impl CreditHistory {
+    /// Records the balance of `account` and returns the change since the
+    /// previous snapshot, none on the first one. `total_usage` only grows
+    /// with spend and `total_credits` only with purchases, so top-ups do not
+    /// count as negative spend.
+    fn observe(
+        &mut self,
+        account: &str,
+        metrics: &OpenRouterMetrics,
+        now: DateTime<Utc>,
+    ) -> CreditChange {
+        let snapshots = self.accounts.entry(account.to_string()).or_default();
+        let change = snapshots
+            .last()
+            .map(|previous| CreditChange {
+                spent: (metrics.total_usage - previous.total_usage).max(0.0),
+                purchased: (metrics.total_credits - previous.total_credits).max(0.0),
+            })
+            .unwrap_or_default();
+        snapshots.push(CreditSnapshot {
+            at: now,
+            total_credits: metrics.total_credits,
+            total_usage: metrics.total_usage,
+        });
+
+        // The newest snapshot before the longest window is its baseline.
+        let (_, longest) = SPEND_WINDOWS[SPEND_WINDOWS.len() - 1];
+        let baseline = snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.at <= now - longest)
+            .unwrap_or(0);
+        snapshots.drain(..baseline);
+        change
+    }
+
+    /// Spend of `account` over the `window` before `now`. `None` until the
+    /// snapshots reach back that far, or when the last snapshot before the
+    /// window is more than a window older than its start, e.g. after
+    /// downtime.
+    fn recent_spend(&self, account: &str, window: TimeDelta, now: DateTime<Utc>) -> Option<f64> {
+        let snapshots = self.accounts.get(account)?;
+        let latest = snapshots.last()?;
+        let start = now - window;
+        let baseline = snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.at <= start)?;
+        if start - baseline.at > window {
+            return None;
+        }
+        Some((latest.total_usage - baseline.total_usage).max(0.0))
+    }
+
+    /// Spend and purchases of each account, derived from its balance and
+    /// the earlier snapshots.
+    fn spend_samples(
+        &mut self,
+        credits: &BTreeMap<String, OpenRouterMetrics>,
+        now: DateTime<Utc>,
+    ) -> Vec<Sample> {
+        let mut samples = Vec::new();
+        for (account, metrics) in credits {
+            let change = self.observe(account, metrics, now);
+            if change.purchased > 0.0 {
+                info!(
+                    account = %account,
+                    purchased = %change.purchased,
+                    total_credits = %metrics.total_credits,
+                    "OpenRouter credits purchased"
+                );
+            }
+            let mut account_samples = vec![
+                Sample::counter_f64(&CREDITS_SPENT, change.spent),
+                Sample::counter(&CREDITS_PURCHASES, u64::from(change.purchased > 0.0)),
+                Sample::counter_f64(&CREDITS_PURCHASED, change.purchased),
+            ];
+            for (window, length) in SPEND_WINDOWS {
+                if let Some(spend) = self.recent_spend(account, length, now) {
+                    account_samples.push(
+                        Sample::f64(&CREDITS_RECENT_SPEND, spend).with_attribute("window", window),
+                    );
+                }
+            }
+            samples.extend(
+                account_samples
+                    .into_iter()
+                    .map(|sample| sample.with_attribute("account", account.clone())),
+            );
+        }
+        samples
+    }
+}
+
+pub struct OpenRouterCollector {
+    config: OpenRouterConfig,
+    client: OpenRouterClient,
+    store: StateStore,
+    /// Activity totals by account, loaded from the state store on the first
+    /// run.
+    reported_activity: Mutex<Option<ReportedActivity>>,
+    /// Balance snapshots, loaded from the state store on the first run.
+    credit_history: Mutex<Option<CreditHistory>>,
+}
+
+impl OpenRouterCollector {
+    pub fn new(
+        config: OpenRouterConfig,
+        store: StateStore,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Self> {
+        let client = OpenRouterClient::new(config.timeout)?.with_retry(retry);
+        Ok(Self {
+            config,
+            client,
+            store,
+            reported_activity: Mutex::default(),
+            credit_history: Mutex::default(),
+        })
+    }
+
+    /// Fetches the account credits and every API key with a provisioning key.
+    async fn fetch_listing(
+        &self,
+        key: &OpenRouterProvisioningKeyConfig,
+    ) -> anyhow::Result<(OpenRouterMetrics, Vec<OpenRouterApiKey>)> {
+        let provisioning_key = std::env::var(&key.api_key_env)
+            .with_context(|| format!("{} environment variable not set", key.api_key_env))?;
+        futures_util::future::try_join(
+            self.client.fetch_credits(&provisioning_key),
+            self.client.fetch_keys(&provisioning_key),
+        )
+        .await
+    }
+
+    async fn fetch_activity(
+        &self,
+        key: &OpenRouterProvisioningKeyConfig,
+    ) -> anyhow::Result<Vec<OpenRouterActivity>> {
+        let provisioning_key = std::env::var(&key.api_key_env)
+            .with_context(|| format!("{} environment variable not set", key.api_key_env))?;
+        self.client.fetch_activity(&provisioning_key).await
+    }
+
+    /// A copy of the state saved under `name`, loaded on first use. Runs
+    /// update the copy and put it back once it has been saved.
+    fn state<T: Clone + DeserializeOwned + Default>(
+        &self,
+        state: &Mutex<Option<T>>,
+        name: &str,
+    ) -> T {
+        state
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| self.store.load(name))
+            .clone()
+    }
+
+    /// Activity increments of every account. The run fails when no account's
+    /// activity could be read, so stale activity counters show up in
+    /// `collector.runs`.
+    fn account_activity(
+        &self,
+        reported: &mut ReportedActivity,
+        keys: &[OpenRouterProvisioningKeyConfig],
+        results: Vec<anyhow::Result<Vec<OpenRouterActivity>>>,
+        today: NaiveDate,
+    ) -> anyhow::Result<Vec<Sample>> {
+        let mut samples = Vec::new();
+        let mut succeeded = false;
+        let mut last_error = None;
+        for (key, result) in keys.iter().zip(results) {
+            match result {
+                Ok(activity) => {
+                    info!(
+                        account = %key.account,
+                        rows = activity.len(),
+                        "Fetched OpenRouter activity"
+                    );
+                    succeeded = true;
+                    samples.extend(self.activity_increments(
+                        reported,
+                        &key.account,
+                        &activity,
+                        today,
+                    ));
+                }
+                Err(e) => {
+                    error!(
+                        account = %key.account,
+                        error = %e,
+                        "Failed to fetch OpenRouter activity"
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !succeeded => Err(e.context("Failed to fetch OpenRouter activity")),
+            _ => Ok(samples),
+        }
+    }
+
+    /// Counter increments for the daily activity of `account`. The first run
+    /// only records the history as reported unless `activity_backfill` is
+    /// set, so the counters start from zero.
+    fn activity_increments(
+        &self,
+        reported: &mut ReportedActivity,
+        account: &str,
+        activity: &[OpenRouterActivity],
+        today: NaiveDate,
+    ) -> Vec<Sample> {
+        let first_run = !reported.contains_key(account);
+        let days = reported.entry(account.to_string()).or_default();
+        let oldest = (today - TimeDelta::days(ACTIVITY_HISTORY_DAYS)).to_string();
+        days.retain(|date, _| *date >= oldest);
+
+        let mut samples = Vec::new();
+        for activity in activity {
+            let day = days.entry(activity.date.clone()).or_default();
+            samples.extend(into_increments(activity_samples(activity), day));
+        }
+
+        if first_run && !self.config.activity_backfill {
+            for sample in &mut samples {
+                sample.value = match sample.value {
+                    Value::CounterF64(_) => Value::CounterF64(0.0),
+                    _ => Value::Counter(0),
+                };
+            }
+        }
+        samples
+            .into_iter()
+            .map(|sample| sample.with_attribute("account", account.to_string()))
+            .collect()
+    }
+
+    /// Fetches the account credits and the key details with one key.
+    async fn fetch(
+        &self,
+        key: &OpenRouterKeyConfig,
+    ) -> anyhow::Result<(OpenRouterMetrics, OpenRouterKey)> {
+        let api_key = std::env::var(&key.api_key_env)
+            .with_context(|| format!("{} environment variable not set", key.api_key_env))?;
+        futures_util::future::try_join(
+            self.client.fetch_credits(&api_key),
+            self.client.fetch_key(&api_key),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Collector for OpenRouterCollector {
+    fn name(&self) -> &'static str {
+        "openrouter"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenRouter"
+    }
+
+    fn meter_name(&self) -> &'static str {
+        "openrouter-credits"
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn labels(&self) -> &BTreeMap<String, String> {
+        &self.config.labels
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    #[instrument(name = "openrouter_credits_run", skip_all, err)]
+    async fn collect(&self) -> anyhow::Result<Vec<Sample>> {
+        info!("Fetching OpenRouter credits");
+
+        let keys = self.config.keys();
+        let provisioned = |enabled: bool| {
+            if enabled {
+                self.config.provisioning_keys.as_slice()
+            } else {
+                &[]
+            }
+        };
+        let listed = provisioned(self.config.list_keys);
+        let activity_keys = provisioned(self.config.activity);
+        let (results, listings, activity) = futures_util::future::join3(
+            futures_util::future::join_all(keys.iter().map(|key| self.fetch(key))),
+            futures_util::future::join_all(listed.iter().map(|key| self.fetch_listing(key))),
+            futures_util::future::join_all(
+                activity_keys.iter().map(|key| self.fetch_activity(key)),
+            ),
+        )
+        .await;
+
+        let mut run = RunSamples::default();
+        for (config, result) in keys.iter().zip(results) {
+            run.add_key(config, result);
+        }
+        for (config, result) in listed.iter().zip(listings) {
+            run.add_listing(config, result, self.config.key_limit_warning);
+        }
+        let credits = std::mem::take(&mut run.credits);
+        let mut samples = run.finish()?;
+
+        // State is saved only once every sample is built, so a failed run
+        // leaves it untouched and the next run reports the same increments.
+        let now = Utc::now();
+        let mut history = self.state(&self.credit_history, CREDITS_STATE_NAME);
+        let mut reported = self.state(&self.reported_activity, ACTIVITY_STATE_NAME);
+        samples.extend(history.spend_samples(&credits, now));
+        samples.extend(self.account_activity(
+            &mut reported,
+            activity_keys,
+            activity,
+            now.date_naive(),
+        )?);
+        self.store.save(CREDITS_STATE_NAME, &history)?;
+        if !activity_keys.is_empty() {
+            self.store.save(ACTIVITY_STATE_NAME, &reported)?;
+        }
+        *self.credit_history.lock().unwrap() = Some(history);
+        *self.reported_activity.lock().unwrap() = Some(reported);
+        Ok(samples)
+    }
+}
+
+/// Counters carrying the totals of one day's activity, to be turned into
+/// increments.
+fn activity_samples(activity: &OpenRouterActivity) -> Vec<Sample> {
+    let tokens = [
+        ("prompt", activity.prompt_tokens),
+        ("completion", activity.completion_tokens),
+        ("reasoning", activity.reasoning_tokens),
+    ];
+    let mut samples = vec![
+        Sample::counter(&ACTIVITY_REQUESTS, activity.requests),
+        Sample::counter_f64(&ACTIVITY_SPEND, activity.usage),
+    ];
+    for (token_type, count) in tokens {
+        samples.push(Sample::counter(&ACTIVITY_TOKENS, count).with_attribute("type", token_type));
+    }
+    samples
+        .into_iter()
+        .map(|sample| {
+            sample
+                .with_attribute("model", activity.model.clone())
+                .with_attribute("provider", activity.provider.clone())
+        })
+        .collect()
+}
+
+/// Samples of one run, reporting the credits of each account once. The run
+/// fails only when nothing could be read.
+#[derive(Default)]
+struct RunSamples {
+    samples: Vec<Sample>,
+    /// Balance of each account, as first read in the run.
+    credits: BTreeMap<String, OpenRouterMetrics>,
+    succeeded: bool,
+    last_error: Option<anyhow::Error>,
+}
+
+impl RunSamples {
+    /// Keys of one account see the same balance; only the first is reported.
+    fn add_credits(&mut self, account: &str, metrics: &OpenRouterMetrics) {
+        if let Entry::Vacant(entry) = self.credits.entry(account.to_string()) {
+            entry.insert(metrics.clone());
+            self.samples.extend(
+                credit_samples(metrics)
+                    .into_iter()
+                    .map(|sample| sample.with_attribute("account", account.to_string())),
+            );
+        }
+    }
+
+    fn add_key(
+        &mut self,
+        config: &OpenRouterKeyConfig,
+        result: anyhow::Result<(OpenRouterMetrics, OpenRouterKey)>,
+    ) {
+        let (metrics, key) = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                error!(
+                    key_name = %config.name,
+                    error = %e,
+                    "Failed to fetch OpenRouter credits for key"
+                );
+                self.last_error = Some(e);
+                return;
+            }
+        };
+        self.succeeded = true;
+        info!(
+            key_name = %config.name,
+            account = %config.account(),
+            total_credits = %metrics.total_credits,
+            total_usage = %metrics.total_usage,
+            remaining = %metrics.remaining,
+            key_label = %key.label,
+            key_usage = %key.usage,
+            key_limit_remaining = ?key.limit_remaining,
+            "Fetched OpenRouter credits metrics"
+        );
+        self.add_credits(config.account(), &metrics);
+        self.samples
+            .extend(key_samples(&key).into_iter().map(|sample| {
+                sample
+                    .with_attribute("account", config.account().to_string())
+                    .with_attribute("key_name", config.name.clone())
+            }));
+    }
+
+    fn add_listing(
+        &mut self,
+        config: &OpenRouterProvisioningKeyConfig,
+        result: anyhow::Result<(OpenRouterMetrics, Vec<OpenRouterApiKey>)>,
+        limit_warning: f64,
+    ) {
+        let (metrics, keys) = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                error!(
+                    account = %config.account,
+                    error = %e,
+                    "Failed to list OpenRouter keys"
+                );
+                self.last_error = Some(e);
+                return;
+            }
+        };
+        self.succeeded = true;
+        info!(
+            account = %config.account,
+            keys = keys.len(),
+            total_credits = %metrics.total_credits,
+            total_usage = %metrics.total_usage,
+            "Listed OpenRouter keys"
+        );
+        self.add_credits(&config.account, &metrics);
+        for key in &keys {
+            self.samples.extend(
+                listed_key_samples(key, limit_warning)
+                    .into_iter()
+                    .map(|sample| sample.with_attribute("account", config.account.clone())),
+            );
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<Vec<Sample>> {
+        match self.last_error {
+            Some(e) if !self.succeeded => Err(e),
+            _ => Ok(self.samples),
+        }
+    }
+}
+
+fn credit_samples(metrics: &OpenRouterMetrics) -> Vec<Sample> {
+    vec![
+        Sample::f64(&CREDITS_TOTAL, metrics.total_credits),
+        Sample::f64(&CREDITS_USAGE, metrics.total_usage),
+        Sample::f64(&CREDITS_REMAINING, metrics.remaining),
+    ]
+}
+
+/// Gauges describing a key listed with a provisioning key, labelled with
+/// its `key_hash` and `key_name`. Keys without a limit are never flagged.
+fn listed_key_samples(key: &OpenRouterApiKey, limit_warning: f64) -> Vec<Sample> {
+    let mut samples = vec![
+        Sample::f64(&LISTED_KEY_USAGE, key.usage),
+        Sample::i64(&LISTED_KEY_DISABLED, i64::from(key.disabled)),
+    ];
+    if let Some(limit) = key.limit {
+        samples.push(
+            Sample::f64(&LISTED_KEY_LIMIT, limit)
+                .with_attribute("limit_reset", key.limit_reset.as_deref().unwrap_or("never")),
+        );
+    }
+    if let Some(remaining) = key.limit_remaining {
+        samples.push(Sample::f64(&LISTED_KEY_LIMIT_REMAINING, remaining));
+    }
+    if let Some(used) = key.limit_used() {
+        samples.push(Sample::i64(
+            &LISTED_KEY_NEAR_LIMIT,
+            i64::from(used >= limit_warning),
+        ));
+        samples.push(Sample::i64(&LISTED_KEY_OVER_LIMIT, i64::from(used >= 1.0)));
+    }
+    samples
+        .into_iter()
+        .map(|sample| {
+            sample
+                .with_attribute("key_hash", key.hash.clone())
+                .with_attribute("key_name", key.name.clone())
+        })
+        .collect()
+}
+
+/// Gauges describing `key`, labelled with its `key_label`.
+fn key_samples(key: &OpenRouterKey) -> Vec<Sample> {
+    let mut samples = vec![
+        Sample::f64(&KEY_USAGE, key.usage),
+        Sample::i64(&KEY_IS_FREE_TIER, i64::from(key.is_free_tier)),
+    ];
+    if let Some(limit) = key.limit {
+        samples.push(
+            Sample::f64(&KEY_LIMIT, limit)
+                .with_attribute("limit_reset", key.limit_reset.as_deref().unwrap_or("never")),
+        );
+    }
+    if let Some(remaining) = key.limit_remaining {
+        samples.push(Sample::f64(&KEY_LIMIT_REMAINING, remaining));
+    }
+    if let Some(rate_limit) = &key.rate_limit {
+        samples.push(
+            Sample::i64(&KEY_RATE_LIMIT, rate_limit.requests)
+                .with_attribute("interval", rate_limit.interval.clone()),
+        );
+    }
+    samples
+        .into_iter()
+        .map(|sample| sample.with_attribute("key_label", key.label.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openrouter_metrics_conversion() {
+        let response = OpenRouterCreditsResponse {
+            data: OpenRouterCreditsData {
+                total_credits: 100.0,
+                total_usage: 25.5,
+            },
+        };
+        let metrics: OpenRouterMetrics = response.into();
+        assert_eq!(metrics.total_credits, 100.0);
+        assert_eq!(metrics.total_usage, 25.5);
+        assert_eq!(metrics.remaining, 74.5);
+    }
+
+    #[test]
+    fn test_key_samples_skip_missing_limits() {
+        let response: OpenRouterKeyResponse = serde_json::from_str(
+            r#"{"data": {
+                "label": "sk-or-v1-abc...def",
+                "limit": 20,
+                "usage": 12.5,
+                "limit_remaining": 7.5,
+                "limit_reset": "monthly",
+                "is_free_tier": false,
+                "rate_limit": {"requests": 10, "interval": "10s"}
+            }}"#,
+        )
+        .unwrap();
+        let key = OpenRouterKey::from(response);
+        assert_eq!(key.limit_remaining, Some(7.5));
+
+        let samples = key_samples(&key);
+        let names: Vec<&str> = samples.iter().map(|s| s.descriptor.name).collect();
+        assert_eq!(
+            names,
+            [
+                "openrouter.key.usage",
+                "openrouter.key.is_free_tier",
+                "openrouter.key.limit",
+                "openrouter.key.limit_remaining",
+                "openrouter.key.rate_limit",
+            ]
+        );
+        assert!(samples.iter().all(|s| {
+            s.attributes
+                .contains(&("key_label".into(), "sk-or-v1-abc...def".into()))
+        }));
+
+        let unlimited: OpenRouterKeyResponse = serde_json::from_str(
+            r#"{"data": {"label": "ci", "limit": null, "usage": 1.0, "limit_remaining": null}}"#,
+        )
+        .unwrap();
+        let samples = key_samples(&OpenRouterKey::from(unlimited));
+        assert_eq!(samples.len(), 2);
+    }
+
+    #[test]
+    fn test_credits_are_reported_once_per_account() {
+        let key = |name: &str, account: Option<&str>| OpenRouterKeyConfig {
+            name: name.to_string(),
+            api_key_env: format!("OPENROUTER_KEY_{name}"),
+            account: account.map(str::to_string),
+        };
+        let keys = [
+            key("team-a", Some("acme")),
+            key("team-b", Some("acme")),
+            key("personal", None),
+        ];
+        let fetched = || {
+            let credits = OpenRouterMetrics {
+                total_credits: 100.0,
+                total_usage: 40.0,
+                remaining: 60.0,
+            };
+            let key = OpenRouterKey {
+                label: "sk-or-v1-abc...def".to_string(),
+                limit: None,
+                usage: 20.0,
+                limit_remaining: None,
+                limit_reset: None,
+                is_free_tier: false,
+                rate_limit: None,
+            };
+            Ok((credits, key))
+        };
+
+        let mut run = RunSamples::default();
+        run.add_key(&keys[0], fetched());
+        run.add_key(&keys[1], fetched());
+        run.add_key(&keys[2], Err(anyhow::anyhow!("rejected")));
+        let samples = run.finish().unwrap();
+        let totals: Vec<&Vec<(String, String)>> = samples
+            .iter()
+            .filter(|s| s.descriptor.name == "openrouter.credits.total")
+            .map(|s| &s.attributes)
+            .collect();
+        assert_eq!(totals, [&vec![("account".into(), "acme".into())]]);
+        let key_names: Vec<&str> = samples
+            .iter()
+            .filter(|s| s.descriptor.name == "openrouter.key.usage")
+            .flat_map(|s| &s.attributes)
+            .filter(|(k, _)| k == "key_name")
+            .map(|(_, v)| v.as_str())
+            .collect();
+        assert_eq!(key_names, ["team-a", "team-b"]);
+
+        let mut failed = RunSamples::default();
+        failed.add_key(&keys[2], Err(anyhow::anyhow!("rejected")));
+        assert!(failed.finish().is_err());
+    }
+
+    #[test]
+    fn test_listed_keys_are_flagged_near_and_over_limit() {
+        let response: OpenRouterApiKeysResponse = serde_json::from_str(
+            r#"{"data": [
+                {"hash": "h1", "name": "ci", "label": "sk-or-v1-a...", "disabled": false,
+                 "limit": 10, "limit_remaining": 0.5, "limit_reset": "monthly", "usage": 42.0,
+                 "created_at": "2026-01-01T00:00:00Z", "updated_at": null},
+                {"hash": "h2", "name": "old", "label": "sk-or-v1-b...", "disabled": true,
+                 "limit": 5, "limit_remaining": -1, "limit_reset": null, "usage": 6.0},
+                {"hash": "h3", "name": "dev", "label": "sk-or-v1-c...", "disabled": false,
+                 "limit": null, "limit_remaining": null, "limit_reset": null, "usage": 3.0}
+            ]}"#,
+        )
+        .unwrap();
+        let keys: Vec<OpenRouterApiKey> = response
+            .data
+            .into_iter()
+            .map(OpenRouterApiKey::from)
+            .collect();
+        // The monthly limit counts this month's spend, not the lifetime usage.
+        assert_eq!(keys[0].limit_used(), Some(0.95));
+        assert_eq!(keys[1].limit_used(), Some(1.2));
+        assert_eq!(keys[2].limit_used(), None);
+
+        let flags = |key: &OpenRouterApiKey| -> Vec<(&str, Value)> {
+            listed_key_samples(key, 0.9)
+                .into_iter()
+                .filter(|s| {
+                    s.descriptor.name.ends_with("_limit") || s.descriptor.name.ends_with("disabled")
+                })
+                .map(|s| (s.descriptor.name, s.value))
+                .collect()
+        };
+        assert_eq!(
+            flags(&keys[0]),
+            [
+                ("openrouter.keys.disabled", Value::I64(0)),
+                ("openrouter.keys.near_limit", Value::I64(1)),
+                ("openrouter.keys.over_limit", Value::I64(0)),
+            ]
+        );
+        assert_eq!(
+            flags(&keys[1]),
+            [
+                ("openrouter.keys.disabled", Value::I64(1)),
+                ("openrouter.keys.near_limit", Value::I64(1)),
+                ("openrouter.keys.over_limit", Value::I64(1)),
+            ]
+        );
+        assert_eq!(
+            flags(&keys[2]),
+            [("openrouter.keys.disabled", Value::I64(0))]
+        );
+
+        let mut run = RunSamples::default();
+        let provisioning = OpenRouterProvisioningKeyConfig {
+            account: "acme".to_string(),
+            api_key_env: "OR_PROVISIONING".to_string(),
+        };
+        let credits = OpenRouterMetrics {
+            total_credits: 100.0,
+            total_usage: 51.0,
+            remaining: 49.0,
+        };
+        run.add_listing(&provisioning, Ok((credits, keys)), 0.9);
+        let samples = run.finish().unwrap();
+        assert!(
+            samples
+                .iter()
+                .all(|s| s.attributes.contains(&("account".into(), "acme".into())))
+        );
+        assert_eq!(
+            samples
+                .iter()
+                .filter(|s| s.descriptor.name == "openrouter.credits.total")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_activity_is_summed_across_endpoints() {
+        let response: OpenRouterActivityResponse = serde_json::from_str(
+            r#"{"data": [
+                {"date": "2026-01-30", "model": "openai/gpt-4.1", "model_permaslug": "openai/gpt-4.1-2025-04-14",
+                 "endpoint_id": "a", "provider_name": "OpenAI", "usage": 0.5, "byok_usage_inference": 0,
+                 "requests": 3, "prompt_tokens": 100, "completion_tokens": 40, "reasoning_tokens": 0},
+                {"date": "2026-01-30", "model": "openai/gpt-4.1", "model_permaslug": "openai/gpt-4.1-2025-04-14",
+                 "endpoint_id": "b", "provider_name": "OpenAI", "usage": 0.25, "byok_usage_inference": 0,
+                 "requests": 1, "prompt_tokens": 10, "completion_tokens": 5, "reasoning_tokens": 2},
+                {"date": "2026-01-30", "model": "openai/gpt-4.1", "model_permaslug": "openai/gpt-4.1-2025-04-14",
+                 "endpoint_id": "c", "provider_name": "Azure", "usage": 1.0, "byok_usage_inference": 0,
+                 "requests": 2, "prompt_tokens": 20, "completion_tokens": 10, "reasoning_tokens": 0}
+            ]}"#,
+        )
+        .unwrap();
+        let activity = sum_activity(response.data);
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].provider, "Azure");
+        assert_eq!(activity[1].provider, "OpenAI");
+        assert_eq!(activity[1].requests, 4);
+        assert_eq!(activity[1].prompt_tokens, 110);
+        assert_eq!(activity[1].reasoning_tokens, 2);
+        assert_eq!(activity[1].usage, 0.75);
+    }
+
+    #[test]
+    fn test_activity_backfill_only_on_first_run() {
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let day = |date: &str, requests: u64| OpenRouterActivity {
+            date: date.to_string(),
+            model: "openai/gpt-4.1".to_string(),
+            provider: "OpenAI".to_string(),
+            requests,
+            usage: 1.5,
+            ..OpenRouterActivity::default()
+        };
+        let requests = |samples: Vec<Sample>| -> Vec<Value> {
+            samples
+                .into_iter()
+                .filter(|s| s.descriptor.name == "openrouter.activity.requests")
+                .map(|s| s.value)
+                .collect()
+        };
+        let collector = |backfill: bool| {
+            let config = OpenRouterConfig {
+                activity_backfill: backfill,
+                ..OpenRouterConfig::default()
+            };
+            OpenRouterCollector::new(config, StateStore::default(), RetryPolicy::default()).unwrap()
+        };
+
+        let history = [day("2026-01-30", 4), day("2026-01-31", 2)];
+        let fresh = collector(false);
+        let mut reported = ReportedActivity::new();
+        let samples = fresh.activity_increments(&mut reported, "acme", &history, today);
+        assert_eq!(requests(samples), [Value::Counter(0), Value::Counter(0)]);
+
+        let next = [
+            day("2026-01-30", 4),
+            day("2026-01-31", 2),
+            day("2026-02-01", 5),
+        ];
+        let samples = fresh.activity_increments(&mut reported, "acme", &next, today);
+        assert_eq!(
+            requests(samples),
+            [Value::Counter(0), Value::Counter(0), Value::Counter(5)]
+        );
+
+        let backfilled = collector(true);
+        let mut reported = ReportedActivity::new();
+        let samples = backfilled.activity_increments(&mut reported, "acme", &history, today);
+        assert_eq!(requests(samples), [Value::Counter(4), Value::Counter(2)]);
+        assert!(
+            backfilled
+                .activity_increments(&mut reported, "acme", &history, today)
+                .iter()
+                .all(|s| s.value == Value::Counter(0) || s.value == Value::CounterF64(0.0))
+        );
+    }
+
+    #[test]
+    fn test_activity_fails_only_when_no_account_is_read() {
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let collector = OpenRouterCollector::new(
+            OpenRouterConfig::default(),
+            StateStore::default(),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let keys = ["acme", "globex"].map(|account| OpenRouterProvisioningKeyConfig {
+            account: account.to_string(),
+            api_key_env: format!("{}_PROVISIONING_KEY", account.to_uppercase()),
+        });
+        let activity = || OpenRouterActivity {
+            date: "2026-02-01".to_string(),
+            requests: 1,
+            ..OpenRouterActivity::default()
+        };
+
+        let mut reported = ReportedActivity::new();
+        let samples = collector
+            .account_activity(
+                &mut reported,
+                &keys,
+                vec![Ok(vec![activity()]), Err(anyhow::anyhow!("timed out"))],
+                today,
+            )
+            .unwrap();
+        assert!(!samples.is_empty());
+        assert_eq!(reported.keys().collect::<Vec<_>>(), ["acme"]);
+
+        let mut reported = ReportedActivity::new();
+        let err = collector
+            .account_activity(
+                &mut reported,
+                &keys,
+                vec![
+                    Err(anyhow::anyhow!("timed out")),
+                    Err(anyhow::anyhow!("timed out")),
+                ],
+                today,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to fetch OpenRouter activity");
+        assert!(reported.is_empty());
+
+        assert!(
+            collector
+                .account_activity(&mut reported, &[], Vec::new(), today)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_recent_spend_over_trailing_windows() {
+        let start = "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let balance = |total_usage: f64| OpenRouterMetrics {
+            total_credits: 100.0,
+            total_usage,
+            remaining: 100.0 - total_usage,
+        };
+        let mut history = CreditHistory::default();
+        assert_eq!(
+            history.observe("acme", &balance(10.0), start),
+            CreditChange::default()
+        );
+        for hour in 1..=24 {
+            let now = start + TimeDelta::hours(hour);
+            let change = history.observe("acme", &balance(10.0 + hour as f64), now);
+            assert_eq!(change.spent, 1.0);
+        }
+
+        let now = start + TimeDelta::hours(24);
+        let (_, hour) = SPEND_WINDOWS[0];
+        let (_, day) = SPEND_WINDOWS[1];
+        let (_, week) = SPEND_WINDOWS[2];
+        assert_eq!(history.recent_spend("acme", hour, now), Some(1.0));
+        assert_eq!(history.recent_spend("acme", day, now), Some(24.0));
+        assert_eq!(history.recent_spend("acme", week, now), None);
+        assert_eq!(history.recent_spend("other", hour, now), None);
+
+        // After three days without a run, the hour and the day have no
+        // baseline close enough to their start.
+        let later = now + TimeDelta::days(3);
+        history.observe("acme", &balance(40.0), later);
+        assert_eq!(history.recent_spend("acme", hour, later), None);
+        assert_eq!(history.recent_spend("acme", day, later), None);
+
+        // Snapshots before the week's baseline are dropped.
+        let much_later = later + TimeDelta::days(8);
+        history.observe("acme", &balance(41.0), much_later);
+        assert_eq!(history.accounts["acme"].len(), 2);
+        assert_eq!(history.recent_spend("acme", week, much_later), Some(1.0));
+    }
+
+    #[test]
+    fn test_purchases_do_not_count_as_spend() {
+        let mut history = CreditHistory::default();
+        let start = "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut run = |total_credits: f64, total_usage: f64, minutes: i64| {
+            let credits = BTreeMap::from([(
+                "acme".to_string(),
+                OpenRouterMetrics {
+                    total_credits,
+                    total_usage,
+                    remaining: total_credits - total_usage,
+                },
+            )]);
+            let samples = history.spend_samples(&credits, start + TimeDelta::minutes(minutes));
+            assert!(
+                samples
+                    .iter()
+                    .all(|s| s.attributes.contains(&("account".into(), "acme".into())))
+            );
+            samples
+                .into_iter()
+                .filter(|s| s.descriptor.name != "openrouter.credits.recent_spend")
+                .map(|s| (s.descriptor.name, s.value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            run(10.0, 8.0, 0),
+            [
+                ("openrouter.credits.spent", Value::CounterF64(0.0)),
+                ("openrouter.credits.purchases", Value::Counter(0)),
+                ("openrouter.credits.purchased", Value::CounterF64(0.0)),
+            ]
+        );
+        // A top-up refills the balance while spending goes on.
+        assert_eq!(
+            run(60.0, 9.5, 5),
+            [
+                ("openrouter.credits.spent", Value::CounterF64(1.5)),
+                ("openrouter.credits.purchases", Value::Counter(1)),
+                ("openrouter.credits.purchased", Value::CounterF64(50.0)),
+            ]
+        );
+        assert_eq!(
+            run(60.0, 10.0, 10),
+            [
+                ("openrouter.credits.spent", Value::CounterF64(0.5)),
+                ("openrouter.credits.purchases", Value::Counter(0)),
+                ("openrouter.credits.purchased", Value::CounterF64(0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_openrouter_metrics_zero_usage() {
+        let response = OpenRouterCreditsResponse {
+            data: OpenRouterCreditsData {
+                total_credits: 50.0,
+                total_usage: 0.0,
+            },
+        };
+        let metrics: OpenRouterMetrics = response.into();
+        assert_eq!(metrics.remaining, 50.0);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+// ============================================================================
+// Usage Pace
+// ============================================================================
+
+/// `resets_at` can shift slightly between responses for the same window, so
+/// only a larger move counts as a new window.
+pub const RESET_TOLERANCE: TimeDelta = TimeDelta::seconds(60);
+
+/// Elapsed share of a window below which the pace is too noisy to report.
+const MIN_ELAPSED_FRACTION: f64 = 0.01;
+
+/// Share of the window over which the recent consumption rate is measured.
+const RECENT_FRACTION: f64 = 0.2;
+
+/// How fast a limit window is being used up, relative to the time it has
+/// been open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pace {
+    /// Utilization divided by the elapsed fraction of the window. Above 1.0,
+    /// the average rate so far reaches the limit before the reset.
+    pub pace: f64,
+    /// Utilization at the reset if the rate over the last fifth of the
+    /// window holds, or the average rate before that much history exists.
+    pub projected_utilization: f64,
+}
+
+#[derive(Debug)]
+struct Observations {
+    resets_at: DateTime<Utc>,
+    points: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+/// Recent utilization of each window, kept in memory to project it forward.
+#[derive(Debug, Default)]
+pub struct PaceTracker {
+    windows: BTreeMap<String, Observations>,
+}
+
+impl PaceTracker {
+    /// Records `utilization` (0.0 to 1.0) of the window `key`, which runs
+    /// from `starts_at` to `resets_at`, and returns its pace. `None` early in
+    /// the window, or when `now` lies outside it.
+    pub fn observe(
+        &mut self,
+        key: &str,
+        utilization: f64,
+        starts_at: DateTime<Utc>,
+        resets_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<Pace> {
+        let length = seconds(resets_at - starts_at);
+        let elapsed = seconds(now - starts_at);
+        if length <= 0.0 || elapsed < 0.0 || now > resets_at {
+            return None;
+        }
+
+        let observations = self
+            .windows
+            .entry(key.to_string())
+            .or_insert_with(|| Observations {
+                resets_at,
+                points: VecDeque::new(),
+            });
+        if (resets_at - observations.resets_at).abs() > RESET_TOLERANCE {
+            observations.resets_at = resets_at;
+            observations.points.clear();
+        }
+        let recent = length * RECENT_FRACTION;
+        while observations
+            .points
+            .front()
+            .is_some_and(|&(at, _)| seconds(now - at) > recent)
+        {
+            observations.points.pop_front();
+        }
+        let oldest = observations.points.front().copied();
+        observations.points.push_back((now, utilization));
+
+        let elapsed_fraction = elapsed / length;
+        if elapsed_fraction < MIN_ELAPSED_FRACTION {
+            return None;
+        }
+        let pace = utilization / elapsed_fraction;
+        let projected_utilization = match oldest {
+            Some((at, previous)) if seconds(now - at) >= recent / 2.0 => {
+                let rate = (utilization - previous).max(0.0) / seconds(now - at);
+                utilization + rate * seconds(resets_at - now)
+            }
+            _ => pace,
+        };
+        Some(Pace {
+            pace,
+            projected_utilization,
+        })
+    }
+}
+
+fn seconds(delta: TimeDelta) -> f64 {
+    delta.num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pace_compares_utilization_with_elapsed_time() {
+        let starts_at = "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let resets_at = starts_at + TimeDelta::hours(5);
+        let mut tracker = PaceTracker::default();
+
+        // Too early in the window to say anything.
+        assert!(
+            tracker
+                .observe("w", 0.0, starts_at, resets_at, starts_at)
+                .is_none()
+        );
+
+        // Half the quota after one hour: on course for 2.5x the limit.
+        let now = starts_at + TimeDelta::hours(1);
+        let pace = tracker
+            .observe("w", 0.5, starts_at, resets_at, now)
+            .unwrap();
+        assert!((pace.pace - 2.5).abs() < 1e-9);
+        assert!((pace.projected_utilization - 2.5).abs() < 1e-9);
+
+        // Nothing used in the last 40 minutes: the projection stays put while
+        // the average pace keeps falling.
+        let now = starts_at + TimeDelta::minutes(100);
+        let pace = tracker
+            .observe("w", 0.5, starts_at, resets_at, now)
+            .unwrap();
+        assert!((pace.pace - 1.5).abs() < 1e-9);
+        assert!((pace.projected_utilization - 0.5).abs() < 1e-9);
+
+        assert!(
+            tracker
+                .observe(
+                    "w",
+                    0.5,
+                    starts_at,
+                    resets_at,
+                    resets_at + TimeDelta::seconds(1)
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_new_window_forgets_recent_rate() {
+        let starts_at = "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let resets_at = starts_at + TimeDelta::hours(5);
+        let mut tracker = PaceTracker::default();
+        tracker.observe(
+            "w",
+            0.2,
+            starts_at,
+            resets_at,
+            starts_at + TimeDelta::hours(1),
+        );
+        tracker.observe(
+            "w",
+            0.9,
+            starts_at,
+            resets_at,
+            starts_at + TimeDelta::hours(2),
+        );
+
+        let next_start = resets_at + TimeDelta::minutes(10);
+        let next_reset = next_start + TimeDelta::hours(5);
+        let now = next_start + TimeDelta::hours(1);
+        let pace = tracker
+            .observe("w", 0.1, next_start, next_reset, now)
+            .unwrap();
+        assert!((pace.projected_utilization - pace.pace).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::{
+    Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::metrics::{
+    InstrumentKind, ManualReader, Pipeline, Temporality,
+    data::{AggregatedMetrics, Metric, MetricData, ResourceMetrics},
+    reader::MetricReader,
+};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Pull-based metric reader backing the `/metrics` scrape endpoint.
+#[derive(Debug, Clone)]
+pub struct PrometheusExporter {
+    reader: Arc<ManualReader>,
+}
+
+/// Registers the shared [`ManualReader`] with a meter provider.
+#[derive(Debug)]
+struct SharedReader(Arc<ManualReader>);
+
+impl MetricReader for SharedReader {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.0.register_pipeline(pipeline)
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> OTelSdkResult {
+        self.0.collect(rm)
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.0.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.0.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.0.temporality(kind)
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        Self {
+            reader: Arc::new(ManualReader::builder().build()),
+        }
+    }
+
+    /// Reader to pass to `SdkMeterProvider::builder().with_reader(..)`.
+    pub fn reader(&self) -> impl MetricReader {
+        SharedReader(self.reader.clone())
+    }
+
+    fn render(&self, format: Format) -> anyhow::Result<String> {
+        let mut rm = ResourceMetrics::default();
+        self.reader
+            .collect(&mut rm)
+            .context("Failed to collect metrics")?;
+        Ok(render(&rm, format))
+    }
+
+    /// Binds `addr` and serves `/metrics` from a background task. Binding
+    /// happens up front so an unavailable port fails startup.
+    pub async fn listen(self, addr: SocketAddr) -> anyhow::Result<JoinHandle<()>> {
+        let app = Router::new()
+            .route("/metrics", get(metrics_handler))
+            .with_state(self);
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind Prometheus endpoint to {addr}"))?;
+        info!(address = %addr, "Serving Prometheus metrics");
+        Ok(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!(error = %e, "Prometheus endpoint failed");
+            }
+        }))
+    }
+}
+
+async fn metrics_handler(
+    State(exporter): State<PrometheusExporter>,
+    headers: HeaderMap,
+) -> Response {
+    let accepts_openmetrics = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("application/openmetrics-text"));
+    let (format, content_type) = if accepts_openmetrics {
+        (Format::OpenMetrics, OPENMETRICS_CONTENT_TYPE)
+    } else {
+        (Format::Prometheus, PROMETHEUS_CONTENT_TYPE)
+    };
+
+    match exporter.render(format) {
+        Ok(body) => ([(header::CONTENT_TYPE, content_type)], body).into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to render Prometheus metrics");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+// ============================================================================
+// Exposition Format
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Prometheus text exposition format 0.0.4.
+    Prometheus,
+    /// OpenMetrics 1.0 text format.
+    OpenMetrics,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Gauge,
+    Counter,
+    Histogram,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Gauge => "gauge",
+            Kind::Counter => "counter",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+/// A metric family merged across instrumentation scopes.
+struct Family {
+    kind: Kind,
+    help: String,
+    unit: Option<String>,
+    samples: Vec<String>,
+}
+
+fn render(rm: &ResourceMetrics, format: Format) -> String {
+    let mut families: BTreeMap<String, Family> = BTreeMap::new();
+    for scope in rm.scope_metrics() {
+        for metric in scope.metrics() {
+            match metric.data() {
+                AggregatedMetrics::F64(data) => add_metric(&mut families, metric, data, format),
+                AggregatedMetrics::I64(data) => add_metric(&mut families, metric, data, format),
+                AggregatedMetrics::U64(data) => add_metric(&mut families, metric, data, format),
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (name, family) in &families {
+        let _ = writeln!(out, "# HELP {name} {}", escape_help(&family.help));
+        let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+        if format == Format::OpenMetrics
+            && let Some(unit) = &family.unit
+        {
+            let _ = writeln!(out, "# UNIT {name} {unit}");
+        }
+        for sample in &family.samples {
+            out.push_str(sample);
+            out.push('\n');
+        }
+    }
+    if format == Format::OpenMetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+fn add_metric<T: PromValue>(
+    families: &mut BTreeMap<String, Family>,
+    metric: &Metric,
+    data: &MetricData<T>,
+    format: Format,
+) {
+    let kind = match data {
+        MetricData::Gauge(_) => Kind::Gauge,
+        MetricData::Sum(sum) if sum.is_monotonic() => Kind::Counter,
+        MetricData::Sum(_) => Kind::Gauge,
+        MetricData::Histogram(_) => Kind::Histogram,
+        // Not representable in the classic text formats.
+        MetricData::ExponentialHistogram(_) => return,
+    };
+    let unit = unit_suffix(metric.unit(), kind);
+    let base = metric_name(metric.name(), unit.as_deref());
+    // Prometheus names the counter family after its `_total` sample, while
+    // OpenMetrics names the family without the suffix.
+    let family_name = if kind == Kind::Counter && format == Format::Prometheus {
+        format!("{base}_total")
+    } else {
+        base.clone()
+    };
+
+    let family = families.entry(family_name).or_insert_with(|| Family {
+        kind,
+        help: metric.description().to_string(),
+        unit,
+        samples: Vec::new(),
+    });
+    if family.kind != kind {
+        // Conflicting instrument types under one name cannot be exposed.
+        return;
+    }
+
+    match data {
+        MetricData::Gauge(gauge) => {
+            for point in gauge.data_points() {
+                let labels = labels(
+                    point
+                        .attributes()
+                        .map(|kv| (kv.key.as_str(), kv.value.to_string())),
+                );
+                family
+                    .samples
+                    .push(format!("{base}{labels} {}", point.value().render()));
+            }
+        }
+        MetricData::Sum(sum) => {
+            let name = if kind == Kind::Counter {
+                format!("{base}_total")
+            } else {
+                base.clone()
+            };
+            for point in sum.data_points() {
+                let labels = labels(
+                    point
+                        .attributes()
+                        .map(|kv| (kv.key.as_str(), kv.value.to_string())),
+                );
+                family
+                    .samples
+                    .push(format!("{name}{labels} {}", point.value().render()));
+            }
+        }
+        MetricData::Histogram(histogram) => {
+            for point in histogram.data_points() {
+                let attributes: Vec<(&str, String)> = point
+                    .attributes()
+                    .map(|kv| (kv.key.as_str(), kv.value.to_string()))
+                    .collect();
+                let mut cumulative = 0;
+                let bounds: Vec<f64> = point.bounds().collect();
+                for (i, count) in point.bucket_counts().enumerate() {
+                    cumulative += count;
+                    let le = bounds.get(i).map_or("+Inf".to_string(), |b| b.render());
+                    let labels = labels(
+                        attributes
+                            .iter()
+                            .map(|(k, v)| (*k, v.clone()))
+                            .chain(std::iter::once(("le", le))),
+                    );
+                    family
+                        .samples
+                        .push(format!("{base}_bucket{labels} {cumulative}"));
+                }
+                let labels = labels(attributes.iter().map(|(k, v)| (*k, v.clone())));
+                family
+                    .samples
+                    .push(format!("{base}_sum{labels} {}", point.sum().render()));
+                family
+                    .samples
+                    .push(format!("{base}_count{labels} {}", point.count()));
+            }
+        }
+        MetricData::ExponentialHistogram(_) => {}
+    }
+}
+
+/// Maps an OpenTelemetry unit to the Prometheus name suffix.
+fn unit_suffix(unit: &str, kind: Kind) -> Option<String> {
+    // Annotations such as `{request}` carry no unit.
+    let mut stripped = String::new();
+    let mut depth = 0;
+    for c in unit.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    let suffix = match stripped.as_str() {
+        "" => return None,
+        "1" if kind == Kind::Gauge => "ratio",
+        "1" => return None,
+        "s" => "seconds",
+        "ms" => "milliseconds",
+        "By" => "bytes",
+        "%" => "percent",
+        other => return Some(sanitize(other, false)),
+    };
+    Some(suffix.to_string())
+}
+
+fn metric_name(name: &str, unit: Option<&str>) -> String {
+    let mut name = sanitize(name, true);
+    if let Some(unit) = unit
+        && !name.ends_with(&format!("_{unit}"))
+    {
+        name.push('_');
+        name.push_str(unit);
+    }
+    name
+}
+
+/// Replaces characters outside the Prometheus name charset with `_`.
+fn sanitize(name: &str, allow_colon: bool) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+fn labels<'a>(attributes: impl Iterator<Item = (&'a str, String)>) -> String {
+    let pairs: Vec<String> = attributes
+        .map(|(key, value)| format!("{}=\"{}\"", sanitize(key, false), escape_label(&value)))
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', r"\\").replace('\n', r"\n")
+}
+
+trait PromValue: Copy {
+    fn render(self) -> String;
+}
+
+impl PromValue for f64 {
+    fn render(self) -> String {
+        if self.is_nan() {
+            "NaN".to_string()
+        } else if self.is_infinite() {
+            if self > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl PromValue for i64 {
+    fn render(self) -> String {
+        self.to_string()
+    }
+}
+
+impl PromValue for u64 {
+    fn render(self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::KeyValue;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    /// Returns the provider too, since the reader only holds a weak pipeline.
+    fn exporter_with_samples() -> (PrometheusExporter, SdkMeterProvider) {
+        let exporter = PrometheusExporter::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(exporter.reader())
+            .build();
+        let meter = provider.meter("test");
+
+        meter
+            .f64_gauge("claude.usage.utilization")
+            .with_description("Current Claude usage utilization rate")
+            .with_unit("ratio")
+            .build()
+            .record(
+                0.25,
+                &[
+                    KeyValue::new("metric_name", "five_hour"),
+                    KeyValue::new("team", "a\"b"),
+                ],
+            );
+        meter
+            .f64_gauge("openrouter.credits.total")
+            .with_description("Total OpenRouter credits purchased")
+            .with_unit("USD")
+            .build()
+            .record(100.0, &[]);
+        meter
+            .u64_counter("collector.runs")
+            .with_description("Collection runs")
+            .build()
+            .add(3, &[KeyValue::new("collector", "claude")]);
+        meter
+            .f64_histogram("collector.duration")
+            .with_description("Collection duration")
+            .with_unit("s")
+            .with_boundaries(vec![1.0, 5.0])
+            .build()
+            .record(2.0, &[]);
+        (exporter, provider)
+    }
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let (exporter, _provider) = exporter_with_samples();
+        let body = exporter.render(Format::Prometheus).unwrap();
+
+        assert!(body.contains("# TYPE claude_usage_utilization_ratio gauge\n"));
+        assert!(body.contains(
+            "claude_usage_utilization_ratio{metric_name=\"five_hour\",team=\"a\\\"b\"} 0.25\n"
+        ));
+        assert!(body.contains("# TYPE openrouter_credits_total_USD gauge\n"));
+        assert!(body.contains("openrouter_credits_total_USD 100\n"));
+        assert!(body.contains("# TYPE collector_runs_total counter\n"));
+        assert!(body.contains("collector_runs_total{collector=\"claude\"} 3\n"));
+        assert!(body.contains("collector_duration_seconds_bucket{le=\"1\"} 0\n"));
+        assert!(body.contains("collector_duration_seconds_bucket{le=\"5\"} 1\n"));
+        assert!(body.contains("collector_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+        assert!(body.contains("collector_duration_seconds_count 1\n"));
+        assert!(!body.contains("# EOF"));
+    }
+
+    #[test]
+    fn test_render_openmetrics_text() {
+        let (exporter, _provider) = exporter_with_samples();
+        let body = exporter.render(Format::OpenMetrics).unwrap();
+
+        assert!(body.contains("# TYPE collector_runs counter\n"));
+        assert!(body.contains("collector_runs_total{collector=\"claude\"} 3\n"));
+        assert!(body.contains("# UNIT claude_usage_utilization_ratio ratio\n"));
+        assert!(body.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_metric_name_sanitization() {
+        assert_eq!(
+            metric_name("github_copilot.usage.seconds_to_reset", Some("seconds")),
+            "github_copilot_usage_seconds_to_reset_seconds"
+        );
+        assert_eq!(metric_name("9lives-metric", None), "_9lives_metric");
+        assert_eq!(
+            metric_name("request.duration_seconds", Some("seconds")),
+            "request_duration_seconds"
+        );
+        assert_eq!(unit_suffix("{request}", Kind::Counter), None);
+        assert_eq!(unit_suffix("1", Kind::Gauge), Some("ratio".to_string()));
+    }
+}
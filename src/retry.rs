@@ -0,0 +1,331 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use tracing::{Instrument, field, info_span, warn};
+
+use crate::config::RetryConfig;
+
+// ============================================================================
+// Retry Policy
+// ============================================================================
+
+/// Retries transient upstream failures with exponential backoff and jitter.
+///
+/// Timeouts, connection errors, HTTP 408/429/5xx and unavailable gRPC services
+/// are retried; authentication failures, other 4xx responses and parse errors
+/// are returned immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(&RetryConfig::default())
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            initial_backoff: config.initial_backoff,
+            max_backoff: config.max_backoff,
+        }
+    }
+
+    /// Runs `operation` until it succeeds, fails permanently or runs out of
+    /// attempts. The attempt count is recorded on an `upstream_call` span.
+    pub async fn run<T, F, Fut>(&self, operation: &'static str, mut call: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let span = info_span!("upstream_call", operation, attempts = field::Empty);
+        async {
+            let mut attempt = 1;
+            loop {
+                let result = call().await;
+                let error = match result {
+                    Ok(value) => {
+                        tracing::Span::current().record("attempts", attempt);
+                        return Ok(value);
+                    }
+                    Err(e) => e,
+                };
+
+                let delay = match self.next_delay(attempt, &error) {
+                    Some(delay) => delay,
+                    None => {
+                        tracing::Span::current().record("attempts", attempt);
+                        return Err(error);
+                    }
+                };
+                warn!(
+                    operation,
+                    attempt,
+                    delay = ?delay,
+                    error = %error,
+                    "Upstream call failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Delay before the next attempt, or `None` when the error should be
+    /// returned as is.
+    fn next_delay(&self, attempt: u32, error: &anyhow::Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_retryable(error) {
+            return None;
+        }
+        match retry_after(error) {
+            // Waiting longer than the policy allows would stall the collector.
+            Some(delay) if delay > self.max_backoff => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Exponential backoff with equal jitter: half of the step is fixed and
+    /// the other half random, so concurrent collectors do not retry in sync.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let step = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        let half = step / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+// ============================================================================
+// Error Inspection
+// ============================================================================
+
+/// `Retry-After` delay requested by the server alongside an error status.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryAfter(pub Duration);
+
+impl std::fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "server asked to retry after {}",
+            humantime::format_duration(self.0)
+        )
+    }
+}
+
+fn is_retryable(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return match e.status() {
+                Some(status) => matches!(status.as_u16(), 408 | 429) || status.is_server_error(),
+                None => !e.is_decode(),
+            };
+        }
+        if cause.is::<serde_json::Error>() {
+            return false;
+        }
+        #[cfg(feature = "cookiejar")]
+        if let Some(status) = cause.downcast_ref::<tonic::Status>() {
+            return matches!(
+                status.code(),
+                tonic::Code::Unavailable
+                    | tonic::Code::DeadlineExceeded
+                    | tonic::Code::ResourceExhausted
+                    | tonic::Code::Aborted
+            );
+        }
+        #[cfg(feature = "cookiejar")]
+        if cause.is::<tonic::transport::Error>() {
+            return true;
+        }
+    }
+    false
+}
+
+fn retry_after(error: &anyhow::Error) -> Option<Duration> {
+    // Downcasting an `anyhow::Error` also looks through attached contexts.
+    error
+        .downcast_ref::<RetryAfter>()
+        .map(|retry_after| retry_after.0)
+}
+
+/// Parses a `Retry-After` value given either in seconds or as an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+// ============================================================================
+// HTTP Helper
+// ============================================================================
+
+/// Sends `request` and turns non-2xx responses into errors, see
+/// [`check_status`].
+///
+/// `api` names the upstream in error messages (e.g. `Claude API`).
+pub async fn send(
+    request: reqwest::RequestBuilder,
+    api: &str,
+) -> anyhow::Result<reqwest::Response> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {api}"))?;
+    check_status(response, api)
+}
+
+/// Turns a non-2xx response into an error, keeping any `Retry-After` on 429
+/// and 503 responses for [`RetryPolicy::run`].
+pub fn check_status(response: reqwest::Response, api: &str) -> anyhow::Result<reqwest::Response> {
+    let Err(error) = response.error_for_status_ref() else {
+        return Ok(response);
+    };
+
+    let status = response.status().as_u16();
+    let retry_after = matches!(status, 429 | 503)
+        .then(|| response.headers().get(reqwest::header::RETRY_AFTER))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, Utc::now()));
+    let error = anyhow::Error::new(error);
+    let error = match retry_after {
+        Some(delay) => error.context(RetryAfter(delay)),
+        None => error,
+    };
+    Err(error.context(format!("{api} returned non-2xx status")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(&RetryConfig {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_secs(5),
+        })
+    }
+
+    /// Serves one canned HTTP response per connection, in order.
+    fn serve(responses: &'static [&'static str]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{addr}/")
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::new(&RetryConfig {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        });
+        let first = policy.backoff(1);
+        assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+        let third = policy.backoff(3);
+        assert!(third >= Duration::from_secs(2) && third <= Duration::from_secs(4));
+        assert!(policy.backoff(8) <= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_does_not_retry_parse_errors() {
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<u32> = policy(3)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(serde_json::from_str::<u32>("nope")?)
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_server_errors_honoring_retry_after() {
+        let url = serve(&[
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 0\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+        ]);
+        let client = reqwest::Client::new();
+        let calls = AtomicU32::new(0);
+
+        let body = policy(3)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let response = send(client.get(&url), "Test API").await?;
+                Ok(response.text().await?)
+            })
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_gives_up_on_auth_errors_and_long_retry_after() {
+        let url = serve(&[
+            "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nretry-after: 3600\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        ]);
+        let client = reqwest::Client::new();
+        let calls = AtomicU32::new(0);
+        let policy = policy(3);
+
+        for _ in 0..2 {
+            let result = policy
+                .run("test", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    send(client.get(&url), "Test API").await
+                })
+                .await;
+            assert!(result.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tracing::warn;
+
+use crate::config::StateConfig;
+
+// ============================================================================
+// State Store
+// ============================================================================
+
+/// JSON files in `state.dir` that let collectors pick up where they left off
+/// after a restart. Without a directory, state only lives in memory.
+#[derive(Debug, Clone, Default)]
+pub struct StateStore {
+    dir: Option<PathBuf>,
+}
+
+impl StateStore {
+    pub fn new(config: &StateConfig) -> Self {
+        Self {
+            dir: config.dir.clone(),
+        }
+    }
+
+    /// Whether state survives a restart.
+    pub fn is_persistent(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Loads the state saved under `name`, falling back to the default when
+    /// nothing was saved yet or the file cannot be read.
+    pub fn load<T: DeserializeOwned + Default>(&self, name: &str) -> T {
+        let Some(path) = self.path(name) else {
+            return T::default();
+        };
+        match read(&path) {
+            Ok(Some(state)) => state,
+            Ok(None) => T::default(),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Ignoring unreadable state file");
+                T::default()
+            }
+        }
+    }
+
+    /// Saves `state` under `name`, replacing the previous file atomically.
+    pub fn save<T: Serialize>(&self, name: &str, state: &T) -> anyhow::Result<()> {
+        let Some(path) = self.path(name) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create state directory {}", dir.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(state)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{name}.json")))
+    }
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_round_trip_and_fallbacks() {
+        let dir =
+            std::env::temp_dir().join(format!("claude-usage-metrics-state-{}", std::process::id()));
+        let store = StateStore::new(&StateConfig {
+            dir: Some(dir.clone()),
+        });
+
+        let missing: BTreeMap<String, u64> = store.load("counts");
+        assert!(missing.is_empty());
+
+        let counts = BTreeMap::from([("a".to_string(), 1u64)]);
+        store.save("counts", &counts).unwrap();
+        assert_eq!(store.load::<BTreeMap<String, u64>>("counts"), counts);
+
+        std::fs::write(dir.join("counts.json"), "not json").unwrap();
+        let corrupt: BTreeMap<String, u64> = store.load("counts");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(corrupt.is_empty());
+    }
+
+    #[test]
+    fn test_in_memory_store_saves_nothing() {
+        let store = StateStore::default();
+        assert!(!store.is_persistent());
+        store.save("counts", &1u64).unwrap();
+        assert_eq!(store.load::<u64>("counts"), 0);
+    }
+}
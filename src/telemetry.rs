@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[cfg(feature = "otlp")]
+use anyhow::Context;
+use claude_usage_metrics::collector::{self, CollectorResult, ErrorClass, Sample, Value};
+use claude_usage_metrics::config::TelemetryConfig;
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Gauge, Histogram},
+    trace::TracerProvider as _,
+};
+#[cfg(feature = "otlp")]
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, metrics::SdkMeterProvider, trace::SdkTracerProvider};
+use tracing::error;
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+#[cfg(feature = "prometheus")]
+use crate::prometheus::PrometheusExporter;
+
+// ============================================================================
+// Telemetry
+// ============================================================================
+
+pub struct TelemetryProviders {
+    pub tracer_provider: SdkTracerProvider,
+    pub meter_provider: SdkMeterProvider,
+    /// Scrape endpoint reader, present when `telemetry.prometheus.enabled`.
+    #[cfg(feature = "prometheus")]
+    pub prometheus: Option<PrometheusExporter>,
+}
+
+pub fn init_telemetry(config: &TelemetryConfig) -> Result<TelemetryProviders, anyhow::Error> {
+    let resource = Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .build();
+
+    let tracer_provider = SdkTracerProvider::builder().with_resource(resource.clone());
+    let meter_provider = SdkMeterProvider::builder().with_resource(resource);
+
+    #[cfg(feature = "otlp")]
+    let (tracer_provider, meter_provider) = if config.otlp_enabled {
+        let otlp_endpoint = &config.otlp_endpoint;
+
+        // Create OTLP span exporter using gRPC (tonic)
+        let otlp_exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(otlp_endpoint)
+            .with_timeout(config.export_timeout)
+            .build()
+            .context("Failed to create OTLP span exporter")?;
+
+        // Create metric exporter using gRPC
+        let metric_exporter = MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(otlp_endpoint)
+            .with_timeout(config.export_timeout)
+            .build()
+            .context("Failed to create metric exporter")?;
+
+        // The batch processor exports from its own thread, so spans ending inside
+        // long-running collector tasks never block a runtime worker.
+        (
+            tracer_provider.with_batch_exporter(otlp_exporter),
+            meter_provider.with_periodic_exporter(metric_exporter),
+        )
+    } else {
+        (tracer_provider, meter_provider)
+    };
+
+    #[cfg(feature = "prometheus")]
+    let prometheus = config.prometheus.enabled.then(PrometheusExporter::new);
+    #[cfg(feature = "prometheus")]
+    let meter_provider = match &prometheus {
+        Some(exporter) => meter_provider.with_reader(exporter.reader()),
+        None => meter_provider,
+    };
+
+    let tracer_provider = tracer_provider.build();
+    let meter_provider = meter_provider.build();
+
+    global::set_meter_provider(meter_provider.clone());
+    global::set_tracer_provider(tracer_provider.clone());
+
+    // Initialize tracing subscriber
+    let tracer = tracer_provider.tracer("claude-usage-metrics");
+    let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+
+    tracing_subscriber::registry()
+        .with(telemetry)
+        .with(fmt_layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    Ok(TelemetryProviders {
+        tracer_provider,
+        meter_provider,
+        #[cfg(feature = "prometheus")]
+        prometheus,
+    })
+}
+
+/// Initializes console logging only, for commands that must not export.
+pub fn init_logging() {
+    tracing_subscriber::registry()
+        .with(fmt_layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+}
+
+fn fmt_layer<S>() -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(true)
+        .with_level(true)
+        .with_file(true)
+        .with_line_number(true)
+}
+
+// ============================================================================
+// Sample Recording
+// ============================================================================
+
+/// Instrumentation scope of the tool's own health metrics.
+const SELF_METER_NAME: &str = "claude-usage-metrics";
+
+/// Bucket boundaries for collection durations, in seconds.
+const DURATION_BOUNDARIES: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Health metrics describing each collection run.
+struct RunMetrics {
+    runs: Counter<u64>,
+    duration: Histogram<f64>,
+    last_success: Gauge<f64>,
+}
+
+impl RunMetrics {
+    fn new() -> Self {
+        let meter = global::meter(SELF_METER_NAME);
+        Self {
+            runs: meter
+                .u64_counter("collector.runs")
+                .with_description("Collection runs by outcome and error class")
+                .build(),
+            duration: meter
+                .f64_histogram("collector.duration")
+                .with_description("Time spent collecting from the upstream API")
+                .with_unit("s")
+                .with_boundaries(DURATION_BOUNDARIES.to_vec())
+                .build(),
+            last_success: meter
+                .f64_gauge("collector.last_success_timestamp")
+                .with_description("Unix time of the last successful collection")
+                .with_unit("s")
+                .build(),
+        }
+    }
+}
+
+/// Records collector samples into OpenTelemetry instruments, reusing
+/// instruments across collection runs, along with per-run health metrics.
+pub struct Recorder {
+    f64_gauges: Mutex<HashMap<(&'static str, &'static str), Gauge<f64>>>,
+    i64_gauges: Mutex<HashMap<(&'static str, &'static str), Gauge<i64>>>,
+    counters: Mutex<HashMap<(&'static str, &'static str), Counter<u64>>>,
+    f64_counters: Mutex<HashMap<(&'static str, &'static str), Counter<f64>>>,
+    runs: RunMetrics,
+}
+
+impl Recorder {
+    /// Must be created after the global meter provider is installed.
+    pub fn new() -> Self {
+        Self {
+            f64_gauges: Mutex::default(),
+            i64_gauges: Mutex::default(),
+            counters: Mutex::default(),
+            f64_counters: Mutex::default(),
+            runs: RunMetrics::new(),
+        }
+    }
+
+    /// Records a successful collection, or logs the failure and returns it
+    /// prefixed with the collector's display name.
+    pub fn record_result(&self, outcome: CollectorResult) -> anyhow::Result<()> {
+        let collector = &outcome.collector;
+        let name = KeyValue::new("collector", collector.name());
+        match outcome.result {
+            Ok(samples) => {
+                self.record(collector.meter_name(), &samples);
+                let attributes = [name, KeyValue::new("outcome", "success")];
+                self.runs.runs.add(1, &attributes);
+                self.runs
+                    .duration
+                    .record(outcome.duration.as_secs_f64(), &attributes);
+                self.runs.last_success.record(unix_now(), &attributes[..1]);
+                Ok(())
+            }
+            Err(e) => {
+                let class = ErrorClass::of(&e);
+                error!(
+                    collector = collector.name(),
+                    error_class = %class,
+                    error = %e,
+                    "{} metrics collection failed",
+                    collector.display_name()
+                );
+                let samples =
+                    collector::with_labels(collector.as_ref(), collector.failure_samples(&e));
+                self.record(collector.meter_name(), &samples);
+
+                let mut attributes = vec![name, KeyValue::new("outcome", "failure")];
+                self.runs
+                    .duration
+                    .record(outcome.duration.as_secs_f64(), &attributes);
+                attributes.push(KeyValue::new("error_class", class.as_str()));
+                self.runs.runs.add(1, &attributes);
+                Err(anyhow::anyhow!("{}: {}", collector.display_name(), e))
+            }
+        }
+    }
+
+    pub fn record(&self, meter_name: &'static str, samples: &[Sample]) {
+        let meter = global::meter(meter_name);
+        for sample in samples {
+            let descriptor = sample.descriptor;
+            let attributes: Vec<KeyValue> = sample
+                .attributes
+                .iter()
+                .map(|(k, v)| KeyValue::new(k.clone(), v.clone()))
+                .collect();
+            match sample.value {
+                Value::F64(value) => {
+                    let mut gauges = self.f64_gauges.lock().unwrap();
+                    let gauge = gauges
+                        .entry((meter_name, descriptor.name))
+                        .or_insert_with(|| {
+                            meter
+                                .f64_gauge(descriptor.name)
+                                .with_description(descriptor.description)
+                                .with_unit(descriptor.unit)
+                                .build()
+                        });
+                    gauge.record(value, &attributes);
+                }
+                Value::I64(value) => {
+                    let mut gauges = self.i64_gauges.lock().unwrap();
+                    let gauge = gauges
+                        .entry((meter_name, descriptor.name))
+                        .or_insert_with(|| {
+                            meter
+                                .i64_gauge(descriptor.name)
+                                .with_description(descriptor.description)
+                                .with_unit(descriptor.unit)
+                                .build()
+                        });
+                    gauge.record(value, &attributes);
+                }
+                Value::Counter(increment) => {
+                    let mut counters = self.counters.lock().unwrap();
+                    let counter = counters
+                        .entry((meter_name, descriptor.name))
+                        .or_insert_with(|| {
+                            meter
+                                .u64_counter(descriptor.name)
+                                .with_description(descriptor.description)
+                                .with_unit(descriptor.unit)
+                                .build()
+                        });
+                    counter.add(increment, &attributes);
+                }
+                Value::CounterF64(increment) => {
+                    let mut counters = self.f64_counters.lock().unwrap();
+                    let counter = counters
+                        .entry((meter_name, descriptor.name))
+                        .or_insert_with(|| {
+                            meter
+                                .f64_counter(descriptor.name)
+                                .with_description(descriptor.description)
+                                .with_unit(descriptor.unit)
+                                .build()
+                        });
+                    counter.add(increment, &attributes);
+                }
+            }
+        }
+    }
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}